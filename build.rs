@@ -1,7 +1,10 @@
 fn main() {
     cxx_build::bridge("src/lib.rs")
+        .include("src")
         .flag_if_supported("-std=c++17")
         .compile("vectorcxx");
 
     println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=src/vectorcxx/controller_callback.h");
+    println!("cargo:rerun-if-changed=src/vectorcxx/arrow_c_data_interface.h");
 }
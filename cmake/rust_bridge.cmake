@@ -119,15 +119,26 @@ function(add_library_rust)
         set(VECTOR_PROFILE_NAME "")
         message(STATUS "No ${VECTOR_PROFILE_NAME} in environment var")
     endif()
+    # the counting global allocator is only for the benchmark builds
+    if(VECTORCXX_ALLOC_STATS)
+        message(STATUS "Count heap allocations of vectorcxx for benchmarking")
+        set(ALLOC_STATS_FEATURE "alloc-stats")
+    else()
+        set(ALLOC_STATS_FEATURE "")
+    endif()
     ## Import Rust target
     corrosion_import_crate(
         MANIFEST_PATH "${CRATE_MANIFEST_PATH}"
-        FEATURES ${MEMORY_ALLOCATOR_FEATURE}
+        FEATURES ${MEMORY_ALLOCATOR_FEATURE} ${ALLOC_STATS_FEATURE}
         LOCKED
         PROFILE ${VECTOR_PROFILE_NAME})
 
     corrosion_add_cxxbridge(${CXXBRIDGE_TARGET} CRATE ${_LIB_NAME} FILES lib.rs)
     set_property(TARGET ${CXXBRIDGE_TARGET} PROPERTY POSITION_INDEPENDENT_CODE ON)
+    # the bridge includes C++ headers declared in lib.rs
+    target_include_directories(${CXXBRIDGE_TARGET} PUBLIC
+            $<BUILD_INTERFACE:${CMAKE_CURRENT_LIST_DIR}/../src>
+            $<INSTALL_INTERFACE:include>)
 
     if(NOT DEFINED VCPKG_TARGET_TRIPLET)
         if(APPLE)
@@ -151,6 +162,10 @@ function(add_library_rust)
             EXPORT ${EXPORT_TARGET_NAME}
             PUBLIC_HEADER DESTINATION include/${CXXBRIDGE_TARGET}
     )
+    install(FILES ${CMAKE_CURRENT_LIST_DIR}/../src/vectorcxx/controller_callback.h
+                  ${CMAKE_CURRENT_LIST_DIR}/../src/vectorcxx/arrow_c_data_interface.h
+            DESTINATION include/vectorcxx
+    )
 
 endfunction(add_library_rust)
 
@@ -0,0 +1,72 @@
+use crate::ffi::AllocationStats;
+
+/*
+Count the heap allocations made by the Rust side, so that the benchmarks are able to tell the
+allocations made per event. Relaxed counters are used to keep the overhead small, still it is
+installed as the global allocator only with the `alloc-stats` feature for the benchmark builds.
+ */
+#[cfg(feature = "alloc-stats")]
+mod counting {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub struct CountingAllocator {
+        pub allocations: AtomicU64,
+        pub allocated_bytes: AtomicU64,
+    }
+
+    #[global_allocator]
+    pub static ALLOCATOR: CountingAllocator = CountingAllocator {
+        allocations: AtomicU64::new(0),
+        allocated_bytes: AtomicU64::new(0),
+    };
+
+    impl CountingAllocator {
+        fn _count(&self, size: usize) {
+            self.allocations.fetch_add(1, Ordering::Relaxed);
+            self.allocated_bytes.fetch_add(size as u64, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self._count(layout.size());
+            System.alloc(layout)
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            self._count(layout.size());
+            System.alloc_zeroed(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        // a reallocation is counted as an allocation of the new size
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            self._count(new_size);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+pub fn get_allocation_stats() -> AllocationStats {
+    use std::sync::atomic::Ordering;
+    AllocationStats {
+        enabled: true,
+        allocations: counting::ALLOCATOR.allocations.load(Ordering::Relaxed),
+        allocated_bytes: counting::ALLOCATOR.allocated_bytes.load(Ordering::Relaxed),
+    }
+}
+
+// the counts are always 0 without the counting allocator
+#[cfg(not(feature = "alloc-stats"))]
+pub fn get_allocation_stats() -> AllocationStats {
+    AllocationStats {
+        enabled: false,
+        allocations: 0,
+        allocated_bytes: 0,
+    }
+}
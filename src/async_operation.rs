@@ -0,0 +1,104 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use cxx::UniquePtr;
+use tracing::info;
+use crate::ffi::{ControllerError, ControllerResult, ErrorKind, OperationCallback, OperationState, OperationStatus};
+
+// the number of completed operations whose status is kept for polling
+const MAX_COMPLETED_OPERATIONS: usize = 1024;
+
+type Operation = Box<dyn FnOnce() -> ControllerResult + Send>;
+
+#[derive(Default)]
+struct OperationStatuses {
+    pending: HashSet<u64>,
+    completed: HashMap<u64, ControllerResult>,
+    // completed operation ids in the order of completion, the oldest ones are evicted first
+    completed_ids: VecDeque<u64>,
+}
+
+impl OperationStatuses {
+    fn complete(&mut self, operation_id: u64, result: ControllerResult) {
+        self.pending.remove(&operation_id);
+        self.completed.insert(operation_id, result);
+        self.completed_ids.push_back(operation_id);
+        while self.completed_ids.len() > MAX_COMPLETED_OPERATIONS {
+            if let Some(evicted_id) = self.completed_ids.pop_front() {
+                self.completed.remove(&evicted_id);
+            }
+        }
+    }
+}
+
+/*
+Run the submitted controller operations one by one in the submitted order on a dedicated worker
+thread, so that the caller is never blocked. The completion of an operation is reported to the
+registered callback on the worker thread, and could also be polled with the operation id.
+ */
+#[derive(Default)]
+pub struct AsyncOperations {
+    last_operation_id: AtomicU64,
+    statuses: Arc<Mutex<OperationStatuses>>,
+    callback: Arc<Mutex<Option<UniquePtr<OperationCallback>>>>,
+    sender: Mutex<Option<Sender<(u64, Operation)>>>,
+}
+
+impl AsyncOperations {
+    pub fn set_callback(&self, callback: UniquePtr<OperationCallback>) {
+        *self.callback.lock().unwrap() = if callback.is_null() { None } else { Some(callback) };
+    }
+
+    // queue an operation and return its id immediately, operation ids start from 1
+    pub fn submit(&self, operation: Operation) -> u64 {
+        let operation_id = self.last_operation_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.statuses.lock().unwrap().pending.insert(operation_id);
+        let mut sender = self.sender.lock().unwrap();
+        let sender = sender.get_or_insert_with(|| self._spawn_worker());
+        if sender.send((operation_id, operation)).is_err() {
+            let err = ControllerError::new(ErrorKind::NotStarted, "", "controller worker thread is not running");
+            self.statuses.lock().unwrap().complete(operation_id, ControllerResult::new(Err(err), 0));
+        }
+        operation_id
+    }
+
+    pub fn status(&self, operation_id: u64) -> OperationStatus {
+        let statuses = self.statuses.lock().unwrap();
+        let (state, result) = if statuses.pending.contains(&operation_id) {
+            (OperationState::Pending, ControllerResult::new(Ok(false), 0))
+        } else if let Some(result) = statuses.completed.get(&operation_id) {
+            (OperationState::Completed, result.clone())
+        } else {
+            (OperationState::NotFound, ControllerResult::new(Ok(false), 0))
+        };
+        OperationStatus { operation_id, state, result }
+    }
+
+    fn _spawn_worker(&self) -> Sender<(u64, Operation)> {
+        let (sender, receiver) = channel::<(u64, Operation)>();
+        let statuses = self.statuses.clone();
+        let callback = self.callback.clone();
+        std::thread::Builder::new()
+            .name("vectorcxx-controller".to_string())
+            .spawn(move || {
+                // the thread exits once the sender is dropped with the controller
+                for (operation_id, operation) in receiver {
+                    let result = operation();
+                    info!("async operation completed: operation_id={} succeed={}", operation_id, result.succeed);
+                    // the callback is invoked before the operation is marked as completed, so that
+                    // a completed status polled means the callback is done as well
+                    if let Some(callback) = callback.lock().unwrap().as_ref() {
+                        callback.on_completed(&OperationStatus {
+                            operation_id,
+                            state: OperationState::Completed,
+                            result: result.clone(),
+                        });
+                    }
+                    statuses.lock().unwrap().complete(operation_id, result);
+                }
+            })
+            .expect("failed to spawn controller worker thread");
+        sender
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use cxx::UniquePtr;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{info_span, Event, Level, Span, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use crate::ffi::{ComponentHealth, ComponentInfo, ComponentStatus, HealthCallback, TopologyStatus};
+
+// the target of the spans the controllers start and reload the topologies in
+pub const CONTROLLER_TARGET: &str = "vectorcxx::controller";
+// the target of the spans vector instruments each component task with, and logs the task errors in
+const TASK_TARGET: &str = "vector::topology::running";
+
+// the monitors of all the controllers by their ids, which are notified with their component task exits
+static MONITORS: Mutex<BTreeMap<u64, Weak<HealthMonitor>>> = Mutex::new(BTreeMap::new());
+static NEXT_CONTROLLER_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Default)]
+struct SpanFields {
+    controller_id: Option<u64>,
+    spawn_id: Option<u64>,
+    component_id: Option<String>,
+}
+
+impl Visit for SpanFields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match field.name() {
+            "controller_id" => self.controller_id = Some(value),
+            "spawn_id" => self.spawn_id = Some(value),
+            _ => {}
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "component_id" {
+            self.component_id = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "component_id" {
+            self.component_id = Some(format!("{:?}", value));
+        }
+    }
+}
+
+// the controller span a component task is spawned in
+struct ControllerSpan {
+    controller_id: u64,
+    spawn_id: u64,
+}
+
+struct ComponentTask {
+    controller_id: u64,
+    spawn_id: u64,
+    component_id: String,
+    // an error is logged in the task span by vector once the task fails or panics
+    failed: bool,
+}
+
+/*
+Find out the component task exits from the spans vector instruments each component task with. A
+task span is closed once the task exits, and it is related to the controller owning it by the
+controller span it is created in, so the controllers in the same process do not see the tasks of
+each other even with the same component ids.
+ */
+pub struct ComponentHealthLayer;
+
+impl<S> Layer<S> for ComponentHealthLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        let target = attrs.metadata().target();
+        if target == CONTROLLER_TARGET {
+            if let (Some(controller_id), Some(spawn_id)) = (fields.controller_id, fields.spawn_id) {
+                span.extensions_mut().insert(ControllerSpan { controller_id, spawn_id });
+            }
+            return;
+        }
+        let component_id = match fields.component_id {
+            Some(component_id) if target == TASK_TARGET => component_id,
+            _ => return,
+        };
+        let controller = span.scope().skip(1).find_map(|parent| {
+            parent
+                .extensions()
+                .get::<ControllerSpan>()
+                .map(|controller| (controller.controller_id, controller.spawn_id))
+        });
+        if let Some((controller_id, spawn_id)) = controller {
+            if let Some(monitor) = _monitor(controller_id) {
+                monitor.task_spawned(&component_id, spawn_id);
+            }
+            span.extensions_mut().insert(ComponentTask { controller_id, spawn_id, component_id, failed: false });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() != Level::ERROR || !metadata.target().starts_with("vector::topology") {
+            return;
+        }
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope {
+                if let Some(task) = span.extensions_mut().get_mut::<ComponentTask>() {
+                    task.failed = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let task = ctx.span(&id).and_then(|span| span.extensions_mut().remove::<ComponentTask>());
+        if let Some(task) = task {
+            if let Some(monitor) = _monitor(task.controller_id) {
+                let health = if task.failed { ComponentHealth::Failed } else { ComponentHealth::Finished };
+                monitor.task_exited(&task.component_id, task.spawn_id, health);
+            }
+        }
+    }
+}
+
+fn _monitor(controller_id: u64) -> Option<Arc<HealthMonitor>> {
+    let mut monitors = MONITORS.lock().unwrap();
+    monitors.retain(|_, monitor| monitor.strong_count() > 0);
+    monitors.get(&controller_id).and_then(Weak::upgrade)
+}
+
+// a controller is dropped once its monitor is gone
+pub fn is_controller_alive(controller_id: u64) -> bool {
+    _monitor(controller_id).is_some()
+}
+
+// the latest task spawned for a component, with its exit once it exits
+struct TaskState {
+    spawn_id: u64,
+    exit: Option<ComponentHealth>,
+}
+
+#[derive(Default)]
+struct HealthState {
+    running: bool,
+    // the exits of the tasks shut down by stopping are not reported
+    stopping: bool,
+    crashed: bool,
+    sources_finished: bool,
+    generation_id: u32,
+    components: Vec<ComponentStatus>,
+    tasks: HashMap<String, TaskState>,
+}
+
+impl HealthState {
+    // the tasks could exit before their components are committed
+    fn apply_task_exits(&mut self) {
+        for component in &mut self.components {
+            if let Some(exit) = self.tasks.get(&component.id).and_then(|task| task.exit) {
+                component.health = exit;
+                component.message = _exit_message(exit).to_string();
+            }
+        }
+    }
+}
+
+fn _exit_message(health: ComponentHealth) -> &'static str {
+    if health == ComponentHealth::Failed {
+        "component task failed"
+    } else {
+        "component task exited"
+    }
+}
+
+/*
+Health status of the running topology and each of its components. A component is running until
+its task exits, and is running again once it is respawned by a reload. The status changes are
+reported to the registered callback on a dedicated notifier thread in the order of the changes,
+since they could be made while the controller locks are held, like starting the topology or
+committing a generation, so the callback is free to call the controller without deadlocks.
+ */
+pub struct HealthMonitor {
+    controller_id: u64,
+    next_spawn_id: AtomicU64,
+    state: Mutex<HealthState>,
+    callback: Arc<Mutex<Option<UniquePtr<HealthCallback>>>>,
+    sender: Mutex<Option<Sender<TopologyStatus>>>,
+}
+
+impl HealthMonitor {
+    pub fn new() -> Arc<Self> {
+        let monitor = Arc::new(Self {
+            controller_id: NEXT_CONTROLLER_ID.fetch_add(1, Ordering::Relaxed),
+            next_spawn_id: AtomicU64::new(1),
+            state: Mutex::new(HealthState::default()),
+            callback: Arc::new(Mutex::new(None)),
+            sender: Mutex::new(None),
+        });
+        MONITORS.lock().unwrap().insert(monitor.controller_id, Arc::downgrade(&monitor));
+        monitor
+    }
+
+    pub fn controller_id(&self) -> u64 {
+        self.controller_id
+    }
+
+    // the span to start or reload the topology in, so that the component tasks spawned are related to this controller
+    pub fn spawn_span(&self) -> Span {
+        let spawn_id = self.next_spawn_id.fetch_add(1, Ordering::Relaxed);
+        info_span!(target: CONTROLLER_TARGET, "controller", controller_id = self.controller_id, spawn_id = spawn_id)
+    }
+
+    pub fn set_callback(&self, callback: UniquePtr<HealthCallback>) {
+        *self.callback.lock().unwrap() = if callback.is_null() { None } else { Some(callback) };
+    }
+
+    pub fn status(&self) -> TopologyStatus {
+        _status(&self.state.lock().unwrap())
+    }
+
+    // the components of a newly committed generation, the ones changed in this generation are respawned
+    pub fn commit_generation(&self, generation_id: u32, components: Vec<ComponentInfo>) {
+        self._update(|state| {
+            let old_components = std::mem::take(&mut state.components);
+            state.components = components
+                .into_iter()
+                .map(|component| {
+                    let old = old_components.iter().find(|old| old.id == component.id);
+                    match old {
+                        Some(old) if component.generation_id != generation_id => old.clone(),
+                        _ => ComponentStatus {
+                            id: component.id,
+                            kind: component.kind,
+                            health: ComponentHealth::Running,
+                            message: String::new(),
+                        },
+                    }
+                })
+                .collect();
+            state.apply_task_exits();
+            state.running = true;
+            state.sources_finished = false;
+            state.generation_id = generation_id;
+            true
+        });
+    }
+
+    // all the components are spawned once the topology is started
+    pub fn set_started(&self) {
+        self._update(|state| {
+            state.running = true;
+            state.stopping = false;
+            state.crashed = false;
+            for component in &mut state.components {
+                component.health = ComponentHealth::Running;
+                component.message.clear();
+            }
+            state.apply_task_exits();
+            true
+        });
+    }
+
+    pub fn set_stopping(&self) {
+        self.state.lock().unwrap().stopping = true;
+    }
+
+    pub fn set_stopped(&self) {
+        self._update(|state| std::mem::replace(&mut state.running, false));
+    }
+
+    pub fn set_crashed(&self) {
+        self._update(|state| !std::mem::replace(&mut state.crashed, true));
+    }
+
+    // the sources of a generation finished, ignored if a newer generation is committed
+    pub fn set_sources_finished(&self, generation_id: u32) {
+        self._update(|state| {
+            let changed = state.generation_id == generation_id && !state.sources_finished;
+            state.sources_finished |= changed;
+            changed
+        });
+    }
+
+    // a component is running again once its task is respawned
+    fn task_spawned(&self, component_id: &str, spawn_id: u64) {
+        self._update(|state| {
+            state.tasks.insert(component_id.to_string(), TaskState { spawn_id, exit: None });
+            match state.components.iter_mut().find(|component| component.id == component_id) {
+                Some(component) if component.health != ComponentHealth::Running => {
+                    component.health = ComponentHealth::Running;
+                    component.message.clear();
+                    true
+                }
+                _ => false,
+            }
+        });
+    }
+
+    // the exits of the tasks replaced by a later spawn are ignored
+    fn task_exited(&self, component_id: &str, spawn_id: u64, health: ComponentHealth) {
+        self._update(|state| {
+            let is_latest = state.tasks.get(component_id).map_or(false, |task| task.spawn_id == spawn_id);
+            if state.stopping || !is_latest {
+                return false;
+            }
+            state.tasks.insert(component_id.to_string(), TaskState { spawn_id, exit: Some(health) });
+            match state.components.iter_mut().find(|component| component.id == component_id) {
+                Some(component) => {
+                    component.health = health;
+                    component.message = _exit_message(health).to_string();
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+
+    // the status is taken under the state lock if it is changed, so that the notifications are in
+    // the same order as the changes
+    fn _update(&self, update: impl FnOnce(&mut HealthState) -> bool) {
+        let mut state = self.state.lock().unwrap();
+        if !update(&mut state) || self.callback.lock().unwrap().is_none() {
+            return;
+        }
+        let mut sender = self.sender.lock().unwrap();
+        let sender = sender.get_or_insert_with(|| self._spawn_notifier());
+        let _ = sender.send(_status(&state));
+    }
+
+    fn _spawn_notifier(&self) -> Sender<TopologyStatus> {
+        let (sender, receiver) = channel::<TopologyStatus>();
+        let callback = self.callback.clone();
+        std::thread::Builder::new()
+            .name("vectorcxx-health".to_string())
+            .spawn(move || {
+                // the thread exits once the sender is dropped with the controller
+                for status in receiver {
+                    if let Some(callback) = callback.lock().unwrap().as_ref() {
+                        callback.on_status_changed(&status);
+                    }
+                }
+            })
+            .expect("failed to spawn controller health notifier thread");
+        sender
+    }
+}
+
+impl Drop for HealthMonitor {
+    // wait for the callback in progress, and no more notifications are made once the monitor is dropped
+    fn drop(&mut self) {
+        self.callback.lock().unwrap().take();
+    }
+}
+
+fn _status(state: &HealthState) -> TopologyStatus {
+    TopologyStatus {
+        running: state.running,
+        crashed: state.crashed,
+        sources_finished: state.sources_finished,
+        generation_id: state.generation_id,
+        components: state.components.clone(),
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use vector::config::ConfigBuilder;
+use crate::ffi::ComponentInfo;
+
+struct Component {
+    id: String,
+    kind: &'static str,
+    // the component config serialized, used to find out whether a component is changed
+    config: Value,
+    inputs: Vec<String>,
+}
+
+fn _components(config_builder: &ConfigBuilder) -> Vec<Component> {
+    let mut components = Vec::new();
+    for (key, source) in &config_builder.sources {
+        components.push(Component {
+            id: key.id().to_string(),
+            kind: "source",
+            config: serde_json::to_value(source).unwrap_or(Value::Null),
+            inputs: Vec::new(),
+        });
+    }
+    for (key, transform) in &config_builder.transforms {
+        components.push(Component {
+            id: key.id().to_string(),
+            kind: "transform",
+            config: serde_json::to_value(transform).unwrap_or(Value::Null),
+            inputs: transform.inputs.iter().cloned().collect(),
+        });
+    }
+    for (key, sink) in &config_builder.sinks {
+        components.push(Component {
+            id: key.id().to_string(),
+            kind: "sink",
+            config: serde_json::to_value(sink).unwrap_or(Value::Null),
+            inputs: sink.inputs.iter().cloned().collect(),
+        });
+    }
+    components
+}
+
+/*
+Track the generation when each component is added or changed lately. It is updated with the
+current config after every generation, a component keeps its generation as long as its config
+stays the same.
+ */
+#[derive(Default)]
+pub struct ComponentGenerations {
+    // component id -> (component config, generation id)
+    generations: HashMap<String, (Value, u32)>,
+}
+
+impl ComponentGenerations {
+    pub fn update(&mut self, config_builder: &ConfigBuilder, generation_id: u32) {
+        let mut generations = HashMap::new();
+        for component in _components(config_builder) {
+            let generation = match self.generations.remove(&component.id) {
+                Some((config, generation)) if config == component.config => generation,
+                _ => generation_id,
+            };
+            generations.insert(component.id, (component.config, generation));
+        }
+        self.generations = generations;
+    }
+
+    pub fn component_infos(&self, config_builder: &ConfigBuilder) -> Vec<ComponentInfo> {
+        _components(config_builder)
+            .into_iter()
+            .map(|component| ComponentInfo {
+                generation_id: self.generations.get(&component.id).map_or(0, |(_, generation)| *generation),
+                component_type: component
+                    .config
+                    .get("type")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                id: component.id,
+                kind: component.kind.to_string(),
+                inputs: component.inputs,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+use vector::config::{ComponentKey, ConfigBuilder, ConfigDiff};
+use crate::ffi::{ComponentDiff, ConfigDiffResult, ControllerError, ControllerResult};
+
+fn _sorted_ids<'a>(keys: impl Iterator<Item = &'a ComponentKey>) -> Vec<String> {
+    let mut ids: Vec<String> = keys.map(|key| key.id().to_string()).collect();
+    ids.sort();
+    ids
+}
+
+impl ComponentDiff {
+    fn new<'a>(
+        to_add: &HashSet<ComponentKey>,
+        to_remove: &HashSet<ComponentKey>,
+        to_change: &HashSet<ComponentKey>,
+        new_keys: impl Iterator<Item = &'a ComponentKey>,
+    ) -> Self {
+        Self {
+            to_add: _sorted_ids(to_add.iter()),
+            to_remove: _sorted_ids(to_remove.iter()),
+            to_change: _sorted_ids(to_change.iter()),
+            unchanged: _sorted_ids(new_keys.filter(|key| !to_add.contains(key) && !to_change.contains(key))),
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            to_add: Vec::new(),
+            to_remove: Vec::new(),
+            to_change: Vec::new(),
+            unchanged: Vec::new(),
+        }
+    }
+}
+
+impl ConfigDiffResult {
+    // the config builder is the new config used for the diff
+    pub fn new(result: Result<(ConfigDiff, ConfigBuilder), ControllerError>, generation_id: u32) -> Self {
+        match result {
+            Ok((diff, config_builder)) => Self {
+                result: ControllerResult::new(Ok(true), generation_id),
+                sources: ComponentDiff::new(
+                    &diff.sources.to_add,
+                    &diff.sources.to_remove,
+                    &diff.sources.to_change,
+                    config_builder.sources.keys(),
+                ),
+                transforms: ComponentDiff::new(
+                    &diff.transforms.to_add,
+                    &diff.transforms.to_remove,
+                    &diff.transforms.to_change,
+                    config_builder.transforms.keys(),
+                ),
+                sinks: ComponentDiff::new(
+                    &diff.sinks.to_add,
+                    &diff.sinks.to_remove,
+                    &diff.sinks.to_change,
+                    config_builder.sinks.keys(),
+                ),
+            },
+            Err(error) => Self {
+                result: ControllerResult::new(Err(error), generation_id),
+                sources: ComponentDiff::empty(),
+                transforms: ComponentDiff::empty(),
+                sinks: ComponentDiff::empty(),
+            },
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt::{Debug, Display};
+use crate::ffi::{ConfigOperation, ControllerError, ErrorKind};
 
 #[derive(Debug)]
 pub enum ConfigAction {
@@ -9,6 +10,24 @@ pub enum ConfigAction {
     EXIT,
 }
 
+// only the actions changing the config are accepted from a config operation
+impl TryFrom<&str> for ConfigAction {
+    type Error = ControllerError;
+
+    fn try_from(action: &str) -> Result<Self, Self::Error> {
+        match action {
+            "add" => Ok(ConfigAction::ADD),
+            "update" => Ok(ConfigAction::UPDATE),
+            "delete" => Ok(ConfigAction::DELETE),
+            _ => Err(ControllerError::new(
+                ErrorKind::ConfigParse,
+                "",
+                format!("unknown config action: {:?}, expected one of add, update and delete", action),
+            )),
+        }
+    }
+}
+
 impl Display for ConfigAction {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         Debug::fmt(self, f)
@@ -19,6 +38,21 @@ pub struct ConfigEvent {
     pub action: ConfigAction,
     pub config_ids: Vec<String>,
     pub config_str: String,
+    // for delete action, also delete the components which would be left with dangling inputs
+    pub cascade: bool,
+}
+
+impl TryFrom<ConfigOperation> for ConfigEvent {
+    type Error = ControllerError;
+
+    fn try_from(operation: ConfigOperation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            action: ConfigAction::try_from(operation.action.as_str())?,
+            config_ids: operation.config_ids,
+            config_str: operation.config,
+            cascade: operation.cascade,
+        })
+    }
 }
 
 impl Debug for ConfigEvent {
@@ -27,6 +61,7 @@ impl Debug for ConfigEvent {
             .field("action", &self.action)
             .field("config_ids", &self.config_ids)
             .field("config_str", &self.config_str)
+            .field("cascade", &self.cascade)
             .finish()
     }
 }
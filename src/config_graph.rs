@@ -0,0 +1,117 @@
+use glob::Pattern;
+use std::collections::BTreeSet;
+use vector::config::ConfigBuilder;
+
+struct Input {
+    // the input as written in config, could be a wildcard like `source_*`
+    input: String,
+    // ids of the components matched by the input
+    matched_ids: BTreeSet<String>,
+}
+
+struct Consumer {
+    id: String,
+    inputs: Vec<Input>,
+}
+
+/*
+Dependency graph of a config builder, which records which sources and transforms are taken as
+input by each transform and sink, with wildcards in `inputs` expanded the same way as vector does.
+ */
+pub struct ConfigGraph {
+    component_ids: BTreeSet<String>,
+    consumers: Vec<Consumer>,
+}
+
+// an input could refer to a component id, a named output of it like `route_a.output_b`, or be a
+// wildcard matching several components
+fn _input_matches(input: &str, id: &str) -> bool {
+    if input == id || input.strip_prefix(id).map_or(false, |rest| rest.starts_with('.')) {
+        return true;
+    }
+    Pattern::new(input).map_or(false, |pattern| pattern.matches(id))
+}
+
+impl ConfigGraph {
+    pub fn new(config_builder: &ConfigBuilder) -> Self {
+        let upstream_ids: Vec<String> = config_builder
+            .sources
+            .keys()
+            .chain(config_builder.transforms.keys())
+            .map(|key| key.id().to_string())
+            .collect();
+        let to_inputs = |inputs: Vec<String>| -> Vec<Input> {
+            inputs
+                .into_iter()
+                .map(|input| Input {
+                    matched_ids: upstream_ids
+                        .iter()
+                        .filter(|id| _input_matches(&input, id))
+                        .cloned()
+                        .collect(),
+                    input,
+                })
+                .collect()
+        };
+
+        let mut consumers = Vec::new();
+        for (key, transform) in &config_builder.transforms {
+            consumers.push(Consumer {
+                id: key.id().to_string(),
+                inputs: to_inputs(transform.inputs.iter().cloned().collect()),
+            });
+        }
+        for (key, sink) in &config_builder.sinks {
+            consumers.push(Consumer {
+                id: key.id().to_string(),
+                inputs: to_inputs(sink.inputs.iter().cloned().collect()),
+            });
+        }
+
+        let component_ids = upstream_ids
+            .into_iter()
+            .chain(config_builder.sinks.keys().map(|key| key.id().to_string()))
+            .collect();
+        Self { component_ids, consumers }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.component_ids.contains(id)
+    }
+
+    /*
+    Return the `(consumer id, input)` pairs which would not match any component once the given
+    components are deleted. An input matching nothing makes vector refuse to build the config.
+     */
+    pub fn dangling_inputs(&self, deleted_ids: &BTreeSet<String>) -> Vec<(String, String)> {
+        let mut dangling = Vec::new();
+        for consumer in &self.consumers {
+            if deleted_ids.contains(&consumer.id) {
+                continue;
+            }
+            for input in &consumer.inputs {
+                if !input.matched_ids.is_empty()
+                    && input.matched_ids.iter().all(|id| deleted_ids.contains(id))
+                {
+                    dangling.push((consumer.id.clone(), input.input.clone()));
+                }
+            }
+        }
+        dangling
+    }
+
+    /*
+    Extend the deleted components with every transform and sink which would be left with a
+    dangling input, until no input is dangling.
+     */
+    pub fn cascade(&self, deleted_ids: &BTreeSet<String>) -> BTreeSet<String> {
+        let mut cascaded = deleted_ids.clone();
+        loop {
+            let dangling = self.dangling_inputs(&cascaded);
+            if dangling.is_empty() {
+                return cascaded;
+            }
+            cascaded.extend(dangling.into_iter().map(|(consumer_id, _)| consumer_id));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::VecDeque;
+use vector::config::ConfigBuilder;
+
+// the number of committed configs kept by default
+pub const DEFAULT_MAX_CONFIG_HISTORY: usize = 10;
+
+/*
+A bounded history of committed configs keyed by generation id, the oldest config is dropped once
+the history is full.
+ */
+pub struct ConfigHistory {
+    max_size: usize,
+    // ordered by generation id
+    configs: VecDeque<(u32, ConfigBuilder)>,
+}
+
+impl Default for ConfigHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONFIG_HISTORY)
+    }
+}
+
+impl ConfigHistory {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            configs: VecDeque::new(),
+        }
+    }
+
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.truncate();
+    }
+
+    pub fn record(&mut self, generation_id: u32, config_builder: ConfigBuilder) {
+        self.configs.push_back((generation_id, config_builder));
+        self.truncate();
+    }
+
+    pub fn get(&self, generation_id: u32) -> Option<&ConfigBuilder> {
+        self.configs
+            .iter()
+            .find(|(id, _)| *id == generation_id)
+            .map(|(_, config_builder)| config_builder)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(u32, ConfigBuilder)> {
+        self.configs.iter()
+    }
+
+    fn truncate(&mut self) {
+        while self.configs.len() > self.max_size {
+            self.configs.pop_front();
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde_json::{json, Value};
+use tempfile::NamedTempFile;
+use vector::config::{self, format, ConfigBuilder};
+
+const STATE_FILE_NAME: &str = "vectorcxx_config_state.json";
+
+// the state file is kept under the `data_dir` of the config, `None` if `data_dir` is not set
+pub fn state_path(config_builder: &ConfigBuilder) -> Option<PathBuf> {
+    config_builder
+        .global
+        .data_dir
+        .as_ref()
+        .map(|data_dir| data_dir.join(STATE_FILE_NAME))
+}
+
+/*
+The last committed config and its generation id persisted in a state file, so that the config
+changed at runtime survives a restart.
+ */
+pub struct ConfigState {
+    pub generation_id: u32,
+    pub config_builder: ConfigBuilder,
+}
+
+impl ConfigState {
+    // write to a temp file in the same folder and then rename it, so that the state file is never
+    // partially written
+    pub fn save(path: &Path, generation_id: u32, config_builder: &ConfigBuilder) -> Result<(), String> {
+        let config = serde_json::to_value(config_builder).map_err(|err| err.to_string())?;
+        let state = json!({
+            "generation_id": generation_id,
+            "config": config,
+        });
+        let dir = path
+            .parent()
+            .ok_or_else(|| format!("invalid state file path: {:?}", path))?;
+        let mut file = NamedTempFile::new_in(dir).map_err(|err| err.to_string())?;
+        file.write_all(state.to_string().as_bytes())
+            .map_err(|err| err.to_string())?;
+        file.as_file().sync_all().map_err(|err| err.to_string())?;
+        file.persist(path).map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    // return `None` if there is no state file
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let state: Value = serde_json::from_str(&content).map_err(|err| err.to_string())?;
+        let generation_id = state
+            .get("generation_id")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| "generation_id is missing in state file".to_string())?;
+        let config = state
+            .get("config")
+            .ok_or_else(|| "config is missing in state file".to_string())?;
+        let config_builder = format::deserialize(&config.to_string(), config::Format::Json)
+            .map_err(|errors| errors.join(","))?;
+        Ok(Some(Self {
+            generation_id: generation_id as u32,
+            config_builder,
+        }))
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt::{Display, Formatter};
+use crate::ffi::{ConfigResult, ControllerError, ControllerResult, ErrorKind, ValidationResult};
+
+impl ControllerError {
+    pub fn new(kind: ErrorKind, component_id: &str, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            component_id: component_id.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn none() -> Self {
+        Self::new(ErrorKind::None, "", "")
+    }
+
+    // vector reports config errors as a list of messages, they are joined into one message
+    pub fn config_parse(errors: Vec<String>) -> Self {
+        Self::new(ErrorKind::ConfigParse, "", errors.join(","))
+    }
+
+    pub fn config_build(errors: Vec<String>) -> Self {
+        Self::new(ErrorKind::ConfigBuild, "", errors.join(","))
+    }
+
+    pub fn not_started() -> Self {
+        Self::new(ErrorKind::NotStarted, "", "topology is not started")
+    }
+}
+
+impl Display for ControllerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.component_id.is_empty() {
+            write!(f, "{:?}: {}", self.kind, self.message)
+        } else {
+            write!(f, "{:?}: component_id={} {}", self.kind, self.component_id, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ControllerError {}
+
+impl ControllerResult {
+    pub fn new(result: Result<bool, ControllerError>, generation_id: u32) -> Self {
+        match result {
+            Ok(succeed) => Self {
+                succeed,
+                generation_id,
+                error: ControllerError::none(),
+            },
+            Err(error) => Self {
+                succeed: false,
+                generation_id,
+                error,
+            },
+        }
+    }
+}
+
+impl ValidationResult {
+    pub fn new(errors: Vec<ControllerError>, warnings: Vec<String>) -> Self {
+        Self {
+            valid: errors.is_empty(),
+            errors,
+            warnings,
+        }
+    }
+}
+
+impl ConfigResult {
+    pub fn new(result: Result<String, ControllerError>, generation_id: u32) -> Self {
+        match result {
+            Ok(config) => Self {
+                result: ControllerResult::new(Ok(true), generation_id),
+                config,
+            },
+            Err(error) => Self {
+                result: ControllerResult::new(Err(error), generation_id),
+                config: String::new(),
+            },
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use vector::event::{Metric, MetricValue};
+use vector::metrics::Controller;
+use crate::ffi::SinkDrainStats;
+
+#[derive(Default, Clone, Copy)]
+struct SinkCounters {
+    received: f64,
+    sent: f64,
+}
+
+fn _counter_value(metric: &Metric) -> Option<f64> {
+    match metric.value() {
+        MetricValue::Counter { value } => Some(*value),
+        _ => None,
+    }
+}
+
+/*
+Counters of events received and sent by each sink, captured from vector internal metrics. It is
+empty if the internal metrics are not initialized.
+ */
+pub struct SinkCountersSnapshot {
+    counters: HashMap<String, SinkCounters>,
+}
+
+impl SinkCountersSnapshot {
+    pub fn capture(sink_ids: &[String]) -> Self {
+        Self::_capture(|component_id| sink_ids.iter().any(|sink_id| sink_id == component_id))
+    }
+
+    // the counters of all the sinks ever run in the process
+    pub fn capture_all() -> Self {
+        Self::_capture(|_| true)
+    }
+
+    fn _capture(is_captured: impl Fn(&str) -> bool) -> Self {
+        let metrics = Controller::get().map_or_else(|_| Vec::new(), |controller| controller.capture_metrics());
+        let mut counters: HashMap<String, SinkCounters> = HashMap::new();
+        for metric in metrics {
+            if metric.tag_value("component_kind").as_deref() != Some("sink") {
+                continue;
+            }
+            let (component_id, value) = match (metric.tag_value("component_id"), _counter_value(&metric)) {
+                (Some(component_id), Some(value)) if is_captured(&component_id) => (component_id, value),
+                _ => continue,
+            };
+            // a counter could be split into several series by other tags, like `output`
+            let sink_counters = counters.entry(component_id).or_default();
+            match metric.name() {
+                "component_received_events_total" => sink_counters.received += value,
+                "component_sent_events_total" => sink_counters.sent += value,
+                _ => {}
+            }
+        }
+        Self { counters }
+    }
+
+    /*
+    Take the counters of the sinks not run by the topology yet from a later snapshot, the counters
+    of a sink run before by another topology with the same id are excluded this way. The ones of
+    the running sinks are kept.
+     */
+    pub fn update_baseline(&mut self, running_sink_ids: &[String], later: SinkCountersSnapshot) {
+        for (sink_id, counters) in later.counters {
+            if !running_sink_ids.contains(&sink_id) {
+                self.counters.insert(sink_id, counters);
+            }
+        }
+    }
+
+    fn get(&self, sink_id: &str) -> SinkCounters {
+        self.counters.get(sink_id).copied().unwrap_or_default()
+    }
+}
+
+/*
+The events handled by each sink while stopping, between the `before` and `after` snapshots. The
+events to drain are the ones held by the sink when stopping, i.e. received but not sent since the
+topology started it at `baseline`, and the ones received while stopping. The flushed ones are
+sent while stopping, and the rest of them are dropped. Events still buffered before a sink are not
+received by it yet, so they are not counted.
+ */
+pub fn drain_stats(
+    sink_ids: &[String],
+    baseline: &SinkCountersSnapshot,
+    before: &SinkCountersSnapshot,
+    after: &SinkCountersSnapshot,
+) -> Vec<SinkDrainStats> {
+    sink_ids
+        .iter()
+        .map(|sink_id| {
+            let (baseline, before, after) = (baseline.get(sink_id), before.get(sink_id), after.get(sink_id));
+            let held = (before.received - baseline.received) - (before.sent - baseline.sent);
+            let received = after.received - before.received;
+            let flushed = after.sent - before.sent;
+            SinkDrainStats {
+                sink_id: sink_id.clone(),
+                flushed_events: flushed.max(0.0) as u64,
+                dropped_events: (held + received - flushed).max(0.0) as u64,
+            }
+        })
+        .collect()
+}
@@ -1,7 +1,20 @@
+mod async_operation;
+mod component_health;
+mod component_info;
+mod config_diff;
 mod config_event;
+mod config_graph;
+mod config_history;
+mod config_state;
+mod controller_error;
+mod drain_stats;
+mod restart_policy;
 mod topology_controller;
 mod model;
 mod memory_queue_client;
+mod allocation_stats;
+mod record_batch;
+mod projection;
 
 use vector::event::LogEvent;
 use vector::event::Value;
@@ -9,11 +22,316 @@ use vector::event::Value;
 use crate::topology_controller::TopologyController;
 use crate::topology_controller::OneShotTopologyController;
 use crate::memory_queue_client::MemoryQueueClient;
+use crate::allocation_stats::get_allocation_stats;
 use crate::model::CxxLogEvent;
 use std::collections::BTreeMap;
 
 #[cxx::bridge(namespace = "vectorcxx")]
 mod ffi {
+    /**
+     * ControllerError
+     */
+    #[derive(Debug)]
+    enum ErrorKind {
+        None,
+        ConfigParse,
+        ConfigBuild,
+        HealthCheck,
+        ReloadRejected,
+        RollbackFailed,
+        NotStarted,
+        AlreadyStarted,
+        DanglingInput,
+        GenerationNotFound,
+        Exited,
+        // the process-wide logging or metrics could not be initialized
+        InitFailed,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ControllerError {
+        kind: ErrorKind,
+        // id of the component causing the error, empty if the error is not related to a component
+        component_id: String,
+        message: String,
+    }
+
+    // result of a controller operation, `error.kind` is `ErrorKind::None` if succeed
+    #[derive(Debug, Clone)]
+    struct ControllerResult {
+        succeed: bool,
+        // the generation id after the operation is performed
+        generation_id: u32,
+        error: ControllerError,
+    }
+
+    #[derive(Debug, Clone)]
+    struct DeleteConfigResult {
+        result: ControllerResult,
+        // ids of all the components actually removed, including the cascaded ones
+        removed_ids: Vec<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    struct SinkDrainStats {
+        sink_id: String,
+        // events sent out by the sink while stopping
+        flushed_events: u64,
+        // events held by the sink when stopping or received while stopping, but never sent out
+        dropped_events: u64,
+    }
+
+    #[derive(Debug, Clone)]
+    struct StopResult {
+        result: ControllerResult,
+        // false if the deadline is reached before all the components are shut down
+        graceful: bool,
+        sinks: Vec<SinkDrainStats>,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ValidationResult {
+        valid: bool,
+        errors: Vec<ControllerError>,
+        warnings: Vec<String>,
+    }
+
+    // component ids changed in a config diff, all sorted by id
+    #[derive(Debug, Clone)]
+    struct ComponentDiff {
+        to_add: Vec<String>,
+        to_remove: Vec<String>,
+        to_change: Vec<String>,
+        unchanged: Vec<String>,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ConfigDiffResult {
+        result: ControllerResult,
+        sources: ComponentDiff,
+        transforms: ComponentDiff,
+        sinks: ComponentDiff,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ComponentInfo {
+        id: String,
+        // one of "source", "transform" and "sink"
+        kind: String,
+        // the component type in vector, like "http_server" and "remap"
+        component_type: String,
+        // empty for sources
+        inputs: Vec<String>,
+        // the generation when the component is added or changed lately
+        generation_id: u32,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ConfigResult {
+        result: ControllerResult,
+        // config in json format, empty if failed
+        config: String,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ConfigHistoryEntry {
+        generation_id: u32,
+        // config in json format
+        config: String,
+    }
+
+    // an incremental config operation in a batch
+    #[derive(Debug, Clone)]
+    struct ConfigOperation {
+        // one of "add", "update" and "delete"
+        action: String,
+        // config json for "add" and "update"
+        config: String,
+        // component ids for "delete"
+        config_ids: Vec<String>,
+        // see `delete_config`
+        cascade: bool,
+    }
+
+    #[derive(Debug, Clone)]
+    struct BatchConfigResult {
+        result: ControllerResult,
+        // ids of the components added, updated or deleted by all the operations
+        changed_ids: Vec<String>,
+    }
+
+    #[derive(Debug)]
+    enum OperationState {
+        // the operation is queued or running
+        Pending,
+        Completed,
+        // the operation id is unknown, or its status is evicted after being completed for long
+        NotFound,
+    }
+
+    #[derive(Debug, Clone)]
+    struct OperationStatus {
+        operation_id: u64,
+        state: OperationState,
+        // only meaningful when the operation is completed
+        result: ControllerResult,
+    }
+
+    #[derive(Debug)]
+    enum ComponentHealth {
+        Running,
+        // the component task exits without error, like a file source reaching the end of files
+        Finished,
+        // the component task fails or panics
+        Failed,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ComponentStatus {
+        id: String,
+        // one of "source", "transform" and "sink"
+        kind: String,
+        health: ComponentHealth,
+        // describes how the component task exited, empty if running
+        message: String,
+    }
+
+    #[derive(Debug, Clone)]
+    struct TopologyStatus {
+        // false if the topology is not started or stopped
+        running: bool,
+        // true if any component task fails, the topology is not stopped by itself
+        crashed: bool,
+        // true if all the sources of current generation finish
+        sources_finished: bool,
+        generation_id: u32,
+        components: Vec<ComponentStatus>,
+    }
+
+    // restart a crashed topology with the last committed config
+    #[derive(Debug, Clone)]
+    struct RestartPolicy {
+        // the maximum attempts to restart after each crash, 0 disables restarting
+        max_retries: u32,
+        // the backoff before the first attempt, doubled after every failed attempt
+        initial_backoff_ms: u64,
+        max_backoff_ms: u64,
+    }
+
+    #[derive(Debug, Clone)]
+    struct RestartStatus {
+        restarting: bool,
+        // attempts made since the controller is created
+        attempts: u32,
+        // succeeded restarts since the controller is created
+        restarts: u32,
+        // true if the last crash is not recovered after all the attempts
+        gave_up: bool,
+        // the error of the last failed attempt
+        last_error: ControllerError,
+    }
+
+    // the result of the fallible getters of `CxxLogEvent`, the value is only set if it is `Present`
+    #[derive(Debug)]
+    enum ValueStatus {
+        Present,
+        // the key is not found or the value is null
+        Absent,
+        WrongType,
+    }
+
+    #[derive(Debug)]
+    enum FieldType {
+        String,
+        Integer,
+        Double,
+        Boolean,
+        // microseconds since epoch, the same as `CxxLogEvent::get_timestamp`
+        Timestamp,
+        // dumped as a JSON string
+        Object,
+        Array,
+    }
+
+    // a field of the target schema that polled events are projected into
+    #[derive(Debug, Clone)]
+    struct SchemaField {
+        // the same path as the getters of `CxxLogEvent`, like "tags.hostname"
+        path: String,
+        field_type: FieldType,
+        // a missing or null value of a required field is an error unless it has a default value
+        required: bool,
+        has_default: bool,
+        // parsed into `field_type` when the schema is registered, in JSON for objects and arrays
+        default_value: String,
+    }
+
+    // only the member of the field type is set, the others are left as zero values
+    #[derive(Debug, Clone)]
+    struct FieldValue {
+        is_null: bool,
+        string_value: String,
+        integer_value: i64,
+        double_value: f64,
+        boolean_value: bool,
+    }
+
+    #[derive(Debug, Clone)]
+    struct CoercionError {
+        path: String,
+        message: String,
+    }
+
+    #[derive(Debug, Clone)]
+    struct ProjectedEvent {
+        // in the order of the schema fields
+        values: Vec<FieldValue>,
+        // the fields failed to be coerced are null or the default value
+        errors: Vec<CoercionError>,
+    }
+
+    // the result of `poll_record_batch`
+    struct ExportedRecordBatch {
+        // the events exported as the rows of the record batch
+        rows: usize,
+        // the events left out of the record batch, reported as errored to the sources with acknowledgements
+        rejected: Vec<CxxLogEvent>,
+        // the reason of each rejected event, in the same order
+        rejected_errors: Vec<String>,
+    }
+
+    // the heap allocations made by the Rust side since the process starts
+    #[derive(Debug, Clone)]
+    struct AllocationStats {
+        // false unless built with the `alloc-stats` feature, the counts are always 0 then
+        enabled: bool,
+        allocations: u64,
+        allocated_bytes: u64,
+    }
+
+    unsafe extern "C++" {
+        include!("vectorcxx/controller_callback.h");
+
+        type OperationCallback;
+
+        fn on_completed(self: &OperationCallback, status: &OperationStatus);
+
+        type HealthCallback;
+
+        fn on_status_changed(self: &HealthCallback, status: &TopologyStatus);
+    }
+
+    unsafe extern "C++" {
+        include!("vectorcxx/arrow_c_data_interface.h");
+
+        // the structs of the Arrow C Data Interface, which are defined in the global namespace
+        #[namespace = ""]
+        type ArrowArray;
+
+        #[namespace = ""]
+        type ArrowSchema;
+    }
+
     extern "Rust" {
         /**
          * TopologyController
@@ -22,21 +340,95 @@ mod ffi {
 
         fn new_topology_controller() -> Box<TopologyController>;
 
-        fn start(self: &mut TopologyController, topology_config: &str) -> Result<bool>;
+        fn start(self: &mut TopologyController, topology_config: &str) -> ControllerResult;
 
-        fn add_config(self: &mut TopologyController, config: String) -> bool;
+        // start with the config committed before restart if there is one, and persist the committed
+        // config of each generation under `data_dir`
+        fn start_with_persisted_config(self: &mut TopologyController, topology_config: &str) -> ControllerResult;
 
-        fn update_config(self: &mut TopologyController, config: String) -> bool;
+        fn add_config(self: &mut TopologyController, config: String) -> ControllerResult;
 
-        fn delete_config(self: &mut TopologyController, config_ids: Vec<String>) -> bool;
+        fn update_config(self: &mut TopologyController, config: String) -> ControllerResult;
 
-        fn exit(self: &mut TopologyController) -> bool;
+        // apply operations with a single reload, either all of them are committed as one
+        // generation or none of them
+        fn apply_config_batch(self: &mut TopologyController, operations: Vec<ConfigOperation>) -> BatchConfigResult;
 
-        fn stop(self: &mut TopologyController) -> bool;
+        // if `cascade` is false, deleting components still taken as input by others is rejected,
+        // otherwise those components are deleted as well
+        fn delete_config(self: &mut TopologyController, config_ids: Vec<String>, cascade: bool) -> DeleteConfigResult;
+
+        // stop the topology within a default timeout for process exit, the controller could not be
+        // started again afterwards
+        fn exit(self: &mut TopologyController) -> ControllerResult;
+
+        // stop the topology after all the in-flight events are drained, the controller could be
+        // started again afterwards
+        fn stop(self: &mut TopologyController) -> ControllerResult;
+
+        // the same as `stop`, but gives up draining once the timeout is reached, and the
+        // components not shut down yet are aborted
+        fn stop_with_timeout(self: &mut TopologyController, timeout_ms: u64) -> StopResult;
 
         fn get_generation_id(self: &mut TopologyController) -> u32;
 
-        fn handle_config_reload(self: &mut TopologyController, config: &str) -> Result<bool>;
+        fn get_components(self: &TopologyController) -> Vec<ComponentInfo>;
+
+        // the current effective config, which could be used to start a topology
+        fn get_config(self: &TopologyController) -> ConfigResult;
+
+        fn get_config_history(self: &TopologyController) -> Vec<ConfigHistoryEntry>;
+
+        fn set_max_config_history(self: &mut TopologyController, max_size: usize);
+
+        // reload the config of an earlier generation kept in history as a new generation
+        fn rollback_to(self: &mut TopologyController, generation_id: u32) -> ControllerResult;
+
+        fn handle_config_reload(self: &mut TopologyController, config: &str) -> ControllerResult;
+
+        // check whether a full config could be loaded, without changing the running topology
+        fn validate_config(self: &TopologyController, config: &str, run_healthchecks: bool) -> ValidationResult;
+
+        // check whether an incremental config operation could be applied to the current config,
+        // without changing the running topology
+        fn validate_config_operation(
+            self: &TopologyController, operation: ConfigOperation, run_healthchecks: bool
+        ) -> ValidationResult;
+
+        // preview the components to be changed by reloading a full config
+        fn diff_config(self: &TopologyController, config: &str) -> ConfigDiffResult;
+
+        // preview the components to be changed by an incremental config operation
+        fn diff_config_operation(self: &TopologyController, operation: ConfigOperation) -> ConfigDiffResult;
+
+        // the async operations below are run one by one in the submitted order on a worker thread,
+        // they return the operation id immediately without blocking the caller
+        fn start_async(self: &mut TopologyController, topology_config: String) -> u64;
+
+        fn add_config_async(self: &mut TopologyController, config: String) -> u64;
+
+        fn update_config_async(self: &mut TopologyController, config: String) -> u64;
+
+        fn delete_config_async(self: &mut TopologyController, config_ids: Vec<String>, cascade: bool) -> u64;
+
+        fn handle_config_reload_async(self: &mut TopologyController, config: String) -> u64;
+
+        fn get_operation_status(self: &TopologyController, operation_id: u64) -> OperationStatus;
+
+        // the callback is invoked on the worker thread once an async operation completes, a null
+        // callback unregisters the current one
+        fn set_operation_callback(self: &mut TopologyController, callback: UniquePtr<OperationCallback>);
+
+        fn get_topology_status(self: &TopologyController) -> TopologyStatus;
+
+        // the callback is invoked on a dedicated notifier thread of the controller, in the order of the
+        // status changes, a null callback unregisters the current one
+        fn set_health_callback(self: &mut TopologyController, callback: UniquePtr<HealthCallback>);
+
+        // restarting is run as an async operation, so it is not interleaved with other async operations
+        fn set_restart_policy(self: &mut TopologyController, policy: RestartPolicy);
+
+        fn get_restart_status(self: &TopologyController) -> RestartStatus;
     }
 
     extern "Rust" {
@@ -66,6 +458,32 @@ mod ffi {
         // return a field as timestamp with microsecond precision
         fn get_timestamp(self: &CxxLogEvent, key: &str) -> i64;
 
+        // the getters above panic if the key is not found or the value is of another type, the ones
+        // below report it in the status instead
+        fn try_get_integer(self: &CxxLogEvent, key: &str, value: &mut i64) -> ValueStatus;
+
+        fn try_get_boolean(self: &CxxLogEvent, key: &str, value: &mut bool) -> ValueStatus;
+
+        fn try_get_double(self: &CxxLogEvent, key: &str, value: &mut f64) -> ValueStatus;
+
+        fn try_get_timestamp(self: &CxxLogEvent, key: &str, value: &mut i64) -> ValueStatus;
+
+        fn try_get_object_as_string(self: &CxxLogEvent, key: &str, value: &mut String) -> ValueStatus;
+
+        fn try_get_array_as_string(self: &CxxLogEvent, key: &str, value: &mut String) -> ValueStatus;
+
+        // coerce the value like the fields of `poll_projected`, e.g. "42" as integer, integer as
+        // double and RFC 3339 string as timestamp
+        fn coerce_integer(self: &CxxLogEvent, key: &str, value: &mut i64) -> ValueStatus;
+
+        fn coerce_boolean(self: &CxxLogEvent, key: &str, value: &mut bool) -> ValueStatus;
+
+        fn coerce_double(self: &CxxLogEvent, key: &str, value: &mut f64) -> ValueStatus;
+
+        fn coerce_timestamp(self: &CxxLogEvent, key: &str, value: &mut i64) -> ValueStatus;
+
+        fn coerce_string(self: &CxxLogEvent, key: &str, value: &mut String) -> ValueStatus;
+
         fn fields(self: &CxxLogEvent) -> Vec<String>;
 
         fn top_level_fields(self: &CxxLogEvent) -> Vec<String>;
@@ -84,6 +502,49 @@ mod ffi {
         )-> Box<MemoryQueueClient>;
 
         fn poll(self: &mut MemoryQueueClient) -> Vec<CxxLogEvent>;
+
+        // the clone-based polling replaced by `poll`, only for comparing them in the benchmarks
+        fn poll_cloned(self: &mut MemoryQueueClient) -> Vec<CxxLogEvent>;
+
+        // park the caller until a batch of events arrives or the timeout is reached
+        fn poll_timeout(self: &mut MemoryQueueClient, timeout_ms: u64) -> Vec<CxxLogEvent>;
+
+        // coalesce batches up to `max_events` events or `max_bytes` bytes in memory (0 for no limit),
+        // waiting no longer than `max_wait_ms` for them to arrive
+        fn poll_batch(
+            self: &mut MemoryQueueClient, max_events: usize, max_bytes: usize, max_wait_ms: u64
+        ) -> Vec<CxxLogEvent>;
+
+        // true if the queue is closed, so that an empty poll is not just because there is no data yet
+        fn is_closed(self: &MemoryQueueClient) -> bool;
+
+        // export the events as an Arrow record batch in a struct array, whose columns are named by the
+        // dotted paths of `CxxLogEvent::fields`, with the schema inferred if `schema` is null. It fails
+        // without polling if `schema` has an unsupported field type, and the events not convertible to
+        // `schema` are left out of the batch and returned as rejected
+        unsafe fn poll_record_batch(
+            self: &mut MemoryQueueClient,
+            max_events: usize,
+            max_bytes: usize,
+            max_wait_ms: u64,
+            schema: *const ArrowSchema,
+            out_array: *mut ArrowArray,
+            out_schema: *mut ArrowSchema,
+        ) -> Result<ExportedRecordBatch>;
+
+        // register the target schema of `poll_projected`, it fails if a default value could not be
+        // parsed into the field type
+        fn set_schema(self: &mut MemoryQueueClient, fields: Vec<SchemaField>) -> Result<()>;
+
+        // collect events like `poll_batch` and project them into the registered schema
+        fn poll_projected(
+            self: &mut MemoryQueueClient, max_events: usize, max_bytes: usize, max_wait_ms: u64
+        ) -> Result<Vec<ProjectedEvent>>;
+    }
+
+    extern "Rust" {
+        // for benchmarking the allocations made by polling events
+        fn get_allocation_stats() -> AllocationStats;
     }
 
     extern "Rust" {
@@ -94,10 +555,16 @@ mod ffi {
 
         fn new_one_shot_topology_controller() -> Box<OneShotTopologyController>;
 
-        fn start(self: &mut OneShotTopologyController, topology_config: &str) -> Result<bool>;
+        fn start(self: &mut OneShotTopologyController, topology_config: &str) -> ControllerResult;
     }
 }
 
+// the callbacks are invoked from threads other than the caller's, and are required to be thread safe
+unsafe impl Send for ffi::OperationCallback {}
+unsafe impl Sync for ffi::OperationCallback {}
+unsafe impl Send for ffi::HealthCallback {}
+unsafe impl Sync for ffi::HealthCallback {}
+
 pub fn new_topology_controller() -> Box<TopologyController> {
     Box::new(TopologyController::new())
 }
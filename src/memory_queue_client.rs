@@ -1,20 +1,39 @@
 use tracing::trace;
-use vector::event::{EventArray, EventContainer, LogEvent, Value, EventMetadata, EventRef};
+use vector::event::{Event, EventArray, EventContainer, EventRef, EventStatus, Finalizable, LogEvent, Value, EventMetadata};
 use vector::sinks::memory_queue::{MemoryQueueSink, MemoryQueueConfig};
 use vector::sinks::VectorSink;
 use vector::transforms::metric_to_log::{MetricToLog, MetricToLogConfig};
 use vector::LogNamespace;
 use vector::test_util::{random_events_with_stream, random_string};
+use vector_common::byte_size_of::ByteSizeOf;
+use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
 use futures::executor::block_on;
 use crate::CxxLogEvent;
+use crate::ffi::{ArrowArray, ArrowSchema, ExportedRecordBatch, ProjectedEvent, SchemaField};
+use crate::projection::Projection;
+use crate::record_batch::{check_schema, export_record_batch, import_schema, to_record_batch};
 use futures::{stream, Stream, StreamExt};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
 
 pub struct MemoryQueueClient {
     receiver: Option<futures::channel::mpsc::Receiver<EventArray>>,
     metric_to_log: MetricToLog,
+    // set once all the senders of the queue are dropped and all the events are polled
+    closed: bool,
+    // the runtime for waiting events with timeout, created on first use
+    rt: Option<Runtime>,
+    // events of a batch split by `poll_batch`, returned before any new batch
+    pending: VecDeque<CxxLogEvent>,
+    // the target schema of `poll_projected`
+    projection: Option<Projection>,
 }
 
+// the finalizers are taken, so that the status is not overwritten when the event is dropped
+fn _nack(event: &mut CxxLogEvent) {
+    event.log_event.take_finalizers().update_status(EventStatus::Errored);
+}
 
 fn random_json_events(
     len: usize,
@@ -89,7 +108,11 @@ impl MemoryQueueClient {
         } else {
             MemoryQueueClient {
                 receiver,
-                metric_to_log
+                metric_to_log,
+                closed: false,
+                rt: None,
+                pending: VecDeque::new(),
+                projection: None,
             }
         }
     }
@@ -98,7 +121,7 @@ impl MemoryQueueClient {
         let config = MemoryQueueConfig {
             rate: None,
             acknowledgements: Default::default(),
-            queue_size: Some(queue_size)
+            queue_size: Some(queue_size),
         };
 
         let sink = MemoryQueueSink::new(config);
@@ -114,33 +137,221 @@ impl MemoryQueueClient {
             LogNamespace::Legacy,
             metric_config.metric_tag_values,
         );
-        MemoryQueueClient { receiver, metric_to_log }
+        MemoryQueueClient {
+            receiver,
+            metric_to_log,
+            closed: false,
+            rt: None,
+            pending: VecDeque::new(),
+            projection: None,
+        }
     }
 
+    // return the events of a batch if there is one, or an empty vector immediately
     pub fn poll(&mut self) -> Vec<CxxLogEvent> {
+        if !self.pending.is_empty() {
+            return self.pending.drain(..).collect();
+        }
+        let mut events: Vec<CxxLogEvent> = Vec::new();
+
+        if let Some(rx) = &mut self.receiver {
+            match rx.try_next() {
+                Ok(Some(value)) => events = self._to_cxx_events(value),
+                Ok(None) => self.closed = true,
+                // no events in queue yet
+                Err(_) => {}
+            }
+        }
+
+        events
+    }
+
+    // the same as `poll` with the events cloned out of the batch as polling did before, only for
+    // benchmarking the polling against it
+    pub fn poll_cloned(&mut self) -> Vec<CxxLogEvent> {
+        if !self.pending.is_empty() {
+            return self.pending.drain(..).collect();
+        }
         let mut events: Vec<CxxLogEvent> = Vec::new();
 
         if let Some(rx) = &mut self.receiver {
             match rx.try_next() {
-                Ok(Some(value)) => {
-                    events.reserve(value.len());
-                    value.iter_events().for_each(|event_ref| {
-                        match event_ref
-                        {
-                            EventRef::Log(log) => events.push(CxxLogEvent { log_event: log.clone() }),
-                            EventRef::Metric(metric) => events.push(CxxLogEvent { log_event: self.metric_to_log.transform_one(metric.clone()).unwrap() }),
-                            _ => ()
-                        }
-                    }
-                    );
+                Ok(Some(value)) => events = self._to_cxx_events_cloned(&value),
+                Ok(None) => self.closed = true,
+                Err(_) => {}
+            }
+        }
+
+        events
+    }
+
+    // wait for a batch of events until the timeout is reached, an empty vector is returned if there
+    // is no events before the deadline or the queue is closed
+    pub fn poll_timeout(&mut self, timeout_ms: u64) -> Vec<CxxLogEvent> {
+        if !self.pending.is_empty() {
+            return self.pending.drain(..).collect();
+        }
+        match self._next_batch(Duration::from_millis(timeout_ms)) {
+            Some(value) => self._to_cxx_events(value),
+            None => Vec::new(),
+        }
+    }
+
+    /*
+    Collect events from several batches, until `max_events` events or `max_bytes` bytes in memory
+    are collected, or `max_wait_ms` is passed. A batch exceeding the limits is split, and the rest
+    is returned first by the next poll. `max_bytes` is not limited if it is 0, and a single event
+    larger than it is still returned.
+     */
+    pub fn poll_batch(&mut self, max_events: usize, max_bytes: usize, max_wait_ms: u64) -> Vec<CxxLogEvent> {
+        let mut events: Vec<CxxLogEvent> = Vec::new();
+        if max_events == 0 {
+            return events;
+        }
+        let deadline = Instant::now() + Duration::from_millis(max_wait_ms);
+        let mut bytes = 0;
+        loop {
+            while let Some(event) = self.pending.pop_front() {
+                let size = event.log_event.size_of();
+                if events.len() >= max_events || (max_bytes > 0 && !events.is_empty() && bytes + size > max_bytes) {
+                    self.pending.push_front(event);
+                    return events;
+                }
+                bytes += size;
+                events.push(event);
+            }
+            if events.len() >= max_events || (max_bytes > 0 && bytes >= max_bytes) {
+                return events;
+            }
+            // batches already in queue are still taken once the deadline is passed
+            match self._next_batch(deadline.saturating_duration_since(Instant::now())) {
+                Some(value) => {
+                    let batch = self._to_cxx_events(value);
+                    self.pending.extend(batch);
                 }
-                Ok(None) => {}
-                Err(e) => {
-                    trace!("failed to poll events: error={:?}", e);
+                None => return events,
+            }
+        }
+    }
+
+    /*
+    Collect events like `poll_batch` and export them as an Arrow record batch through the Arrow C
+    Data Interface, the caller takes the ownership of `out_array` and `out_schema` and releases them.
+    The schema is inferred from the events if `schema` is null, otherwise it is checked before
+    polling. The events not convertible to the schema, like the ones with a null value of a
+    non-nullable field, are left out and returned as rejected, so that they neither block the next
+    polls nor make the other events of the batch lost. The rejected events are reported as errored
+    to the sources with acknowledgements, the same as all the events if the batch fails to export.
+     */
+    pub unsafe fn poll_record_batch(
+        &mut self,
+        max_events: usize,
+        max_bytes: usize,
+        max_wait_ms: u64,
+        schema: *const ArrowSchema,
+        out_array: *mut ArrowArray,
+        out_schema: *mut ArrowSchema,
+    ) -> Result<ExportedRecordBatch, String> {
+        let schema = if schema.is_null() {
+            None
+        } else {
+            let schema = import_schema(schema as *const FFI_ArrowSchema)?;
+            check_schema(&schema)?;
+            Some(schema)
+        };
+        let mut events = self.poll_batch(max_events, max_bytes, max_wait_ms);
+        let exported = to_record_batch(&events, schema).and_then(|(batch, rejected)| {
+            export_record_batch(batch, out_array as *mut FFI_ArrowArray, out_schema as *mut FFI_ArrowSchema)
+                .map(|()| rejected)
+        });
+        match exported {
+            Ok(rejected) => {
+                let rows = events.len() - rejected.len();
+                let mut result = ExportedRecordBatch { rows, rejected: Vec::new(), rejected_errors: Vec::new() };
+                // removed from the highest index, so that the lower ones are not moved by `swap_remove`
+                for (index, error) in rejected.into_iter().rev() {
+                    let mut event = events.swap_remove(index);
+                    _nack(&mut event);
+                    result.rejected.push(event);
+                    result.rejected_errors.push(error);
                 }
+                result.rejected.reverse();
+                result.rejected_errors.reverse();
+                Ok(result)
+            }
+            Err(err) => {
+                events.iter_mut().for_each(_nack);
+                Err(format!("{}, rejected events={}", err, events.len()))
+            }
+        }
+    }
+
+    pub fn set_schema(&mut self, fields: Vec<SchemaField>) -> Result<(), String> {
+        self.projection = Some(Projection::new(fields)?);
+        Ok(())
+    }
+
+    pub fn poll_projected(&mut self, max_events: usize, max_bytes: usize, max_wait_ms: u64) -> Result<Vec<ProjectedEvent>, String> {
+        if self.projection.is_none() {
+            return Err("no schema is registered for projecting events".to_string());
+        }
+        let events = self.poll_batch(max_events, max_bytes, max_wait_ms);
+        let projection = self.projection.as_ref().unwrap();
+        Ok(events.iter().map(|event| projection.project(event)).collect())
+    }
+
+    // wait for the next batch until the timeout is reached, None if timed out or the queue is closed
+    fn _next_batch(&mut self, timeout: Duration) -> Option<EventArray> {
+        let rx = self.receiver.as_mut()?;
+        let rt = self.rt.get_or_insert_with(|| {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .expect("failed to build runtime for memory queue client")
+        });
+        // the queue is always polled once before checking the timeout
+        match rt.block_on(tokio::time::timeout(timeout, rx.next())) {
+            Ok(Some(value)) => Some(value),
+            Ok(None) => {
+                self.closed = true;
+                None
+            }
+            Err(_) => {
+                trace!("no events polled before timeout: timeout={:?}", timeout);
+                None
+            }
+        }
+    }
+
+    // tell whether an empty poll means there would never be events any more
+    pub fn is_closed(&self) -> bool {
+        (self.closed || self.receiver.is_none()) && self.pending.is_empty()
+    }
+
+    // the events are moved out of the batch without cloning
+    fn _to_cxx_events(&self, value: EventArray) -> Vec<CxxLogEvent> {
+        let mut events: Vec<CxxLogEvent> = Vec::with_capacity(value.len());
+        value.into_events().for_each(|event| {
+            match event
+            {
+                Event::Log(log_event) => events.push(CxxLogEvent { log_event }),
+                Event::Metric(metric) => events.push(CxxLogEvent { log_event: self.metric_to_log.transform_one(metric).unwrap() }),
+                _ => ()
             }
         }
+        );
+        events
+    }
 
+    fn _to_cxx_events_cloned(&self, value: &EventArray) -> Vec<CxxLogEvent> {
+        let mut events: Vec<CxxLogEvent> = Vec::with_capacity(value.len());
+        value.iter_events().for_each(|event_ref| match event_ref {
+            EventRef::Log(log) => events.push(CxxLogEvent { log_event: log.clone() }),
+            EventRef::Metric(metric) => {
+                events.push(CxxLogEvent { log_event: self.metric_to_log.transform_one(metric.clone()).unwrap() })
+            }
+            _ => (),
+        });
         events
     }
 }
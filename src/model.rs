@@ -4,6 +4,8 @@ use vector::event_path;
 use vector::event::Value;
 use std::collections::BTreeMap;
 use vector::event::EventMetadata;
+use crate::ffi::{FieldType, FieldValue, ValueStatus};
+use crate::projection::coerce;
 
 pub struct CxxLogEvent {
     pub log_event: LogEvent,
@@ -24,6 +26,11 @@ impl CxxLogEvent {
         }
     }
 
+    // look up the value by the path first, and then by the key as a whole like "tags.hostname[]"
+    pub fn value(&self, key: &str) -> Option<&Value> {
+        self.log_event.get(key).or_else(|| self.log_event.get(event_path!(key)))
+    }
+
     // return a String but not &str, because value string maybe not valid UTF, using as_str() to 
     // handle invalid UTF string and return the correctly parsed string.
     pub fn get_string(&self, key: &str) -> String {
@@ -115,6 +122,68 @@ impl CxxLogEvent {
         value_ref.as_timestamp().unwrap().timestamp_micros()
     }
 
+    pub fn try_get_integer(&self, key: &str, value: &mut i64) -> ValueStatus {
+        self._try_get(key, value, Value::as_integer)
+    }
+
+    pub fn try_get_boolean(&self, key: &str, value: &mut bool) -> ValueStatus {
+        self._try_get(key, value, Value::as_boolean)
+    }
+
+    pub fn try_get_double(&self, key: &str, value: &mut f64) -> ValueStatus {
+        self._try_get(key, value, |v| v.as_float().map(|float| float.into_inner()))
+    }
+
+    pub fn try_get_timestamp(&self, key: &str, value: &mut i64) -> ValueStatus {
+        self._try_get(key, value, |v| v.as_timestamp().map(|timestamp| timestamp.timestamp_micros()))
+    }
+
+    pub fn try_get_object_as_string(&self, key: &str, value: &mut String) -> ValueStatus {
+        self._try_get(key, value, |v| v.is_object().then(|| v.to_string_lossy().into_owned()))
+    }
+
+    pub fn try_get_array_as_string(&self, key: &str, value: &mut String) -> ValueStatus {
+        self._try_get(key, value, |v| v.is_array().then(|| v.to_string_lossy().into_owned()))
+    }
+
+    pub fn coerce_integer(&self, key: &str, value: &mut i64) -> ValueStatus {
+        self._coerce(key, value, FieldType::Integer, |coerced| coerced.integer_value)
+    }
+
+    pub fn coerce_boolean(&self, key: &str, value: &mut bool) -> ValueStatus {
+        self._coerce(key, value, FieldType::Boolean, |coerced| coerced.boolean_value)
+    }
+
+    pub fn coerce_double(&self, key: &str, value: &mut f64) -> ValueStatus {
+        self._coerce(key, value, FieldType::Double, |coerced| coerced.double_value)
+    }
+
+    pub fn coerce_timestamp(&self, key: &str, value: &mut i64) -> ValueStatus {
+        self._coerce(key, value, FieldType::Timestamp, |coerced| coerced.integer_value)
+    }
+
+    pub fn coerce_string(&self, key: &str, value: &mut String) -> ValueStatus {
+        self._coerce(key, value, FieldType::String, |coerced| coerced.string_value)
+    }
+
+    // the output is left untouched unless the value is present and converted
+    fn _try_get<T>(&self, key: &str, output: &mut T, convert: impl FnOnce(&Value) -> Option<T>) -> ValueStatus {
+        match self.value(key) {
+            None | Some(Value::Null) => ValueStatus::Absent,
+            Some(value) => match convert(value) {
+                Some(converted) => {
+                    *output = converted;
+                    ValueStatus::Present
+                }
+                None => ValueStatus::WrongType,
+            },
+        }
+    }
+
+    fn _coerce<T>(&self, key: &str, output: &mut T, field_type: FieldType, pick: impl FnOnce(FieldValue) -> T) -> ValueStatus {
+        self._try_get(key, output, |value| coerce(value, field_type).ok().map(pick))
+    }
+
     /*
     Return all fields of an event.
     Vector uses a depth-first logic to construct and traverse fields, event like
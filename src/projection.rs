@@ -0,0 +1,128 @@
+use chrono::DateTime;
+use vector::event::Value;
+use crate::ffi::{CoercionError, FieldType, FieldValue, ProjectedEvent, SchemaField};
+use crate::CxxLogEvent;
+
+impl FieldValue {
+    pub fn null() -> Self {
+        Self {
+            is_null: true,
+            string_value: String::new(),
+            integer_value: 0,
+            double_value: 0.0,
+            boolean_value: false,
+        }
+    }
+
+    fn string(value: String) -> Self {
+        Self { is_null: false, string_value: value, ..Self::null() }
+    }
+
+    fn integer(value: i64) -> Self {
+        Self { is_null: false, integer_value: value, ..Self::null() }
+    }
+
+    fn double(value: f64) -> Self {
+        Self { is_null: false, double_value: value, ..Self::null() }
+    }
+
+    fn boolean(value: bool) -> Self {
+        Self { is_null: false, boolean_value: value, ..Self::null() }
+    }
+}
+
+/*
+Coerce a non-null value into the field type. Strings are parsed into the other scalar types,
+integers are widened to doubles, and doubles without fraction are narrowed to integers. Any
+value is accepted as a string, objects and arrays are dumped as JSON.
+ */
+pub fn coerce(value: &Value, field_type: FieldType) -> Result<FieldValue, String> {
+    let coerced = match (field_type, value) {
+        (FieldType::String, Value::Bytes(bytes)) => Some(FieldValue::string(String::from_utf8_lossy(bytes).into_owned())),
+        (FieldType::String, value) => Some(FieldValue::string(value.to_string_lossy().into_owned())),
+        (FieldType::Integer, Value::Integer(integer)) => Some(FieldValue::integer(*integer)),
+        (FieldType::Integer, Value::Float(float)) if float.fract() == 0.0 => Some(FieldValue::integer(float.into_inner() as i64)),
+        (FieldType::Integer, Value::Bytes(bytes)) => _parse(bytes).map(FieldValue::integer),
+        (FieldType::Double, Value::Float(float)) => Some(FieldValue::double(float.into_inner())),
+        (FieldType::Double, Value::Integer(integer)) => Some(FieldValue::double(*integer as f64)),
+        (FieldType::Double, Value::Bytes(bytes)) => _parse(bytes).map(FieldValue::double),
+        (FieldType::Boolean, Value::Boolean(boolean)) => Some(FieldValue::boolean(*boolean)),
+        (FieldType::Boolean, Value::Bytes(bytes)) => _parse(bytes).map(FieldValue::boolean),
+        (FieldType::Timestamp, Value::Timestamp(timestamp)) => Some(FieldValue::integer(timestamp.timestamp_micros())),
+        (FieldType::Timestamp, Value::Bytes(bytes)) => std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|text| DateTime::parse_from_rfc3339(text.trim()).ok())
+            .map(|timestamp| FieldValue::integer(timestamp.timestamp_micros())),
+        (FieldType::Object, Value::Object(_)) | (FieldType::Array, Value::Array(_)) => {
+            Some(FieldValue::string(value.to_string_lossy().into_owned()))
+        }
+        _ => None,
+    };
+    coerced.ok_or_else(|| format!("{} value could not be coerced to {:?}", value.kind_str(), field_type))
+}
+
+fn _parse<T: std::str::FromStr>(bytes: &[u8]) -> Option<T> {
+    std::str::from_utf8(bytes).ok().and_then(|text| text.trim().parse().ok())
+}
+
+// the default values of objects and arrays are given in JSON, the others are parsed from strings
+fn _default_value(field: &SchemaField) -> Result<FieldValue, String> {
+    let value = match field.field_type {
+        FieldType::Object | FieldType::Array => serde_json::from_str::<serde_json::Value>(&field.default_value)
+            .map(Value::from)
+            .map_err(|err| format!("default value is not valid JSON: {}", err))?,
+        _ => Value::from(field.default_value.as_str()),
+    };
+    coerce(&value, field.field_type)
+}
+
+/*
+The target schema registered on a memory queue client, the default values are parsed once when
+the schema is registered.
+ */
+pub struct Projection {
+    fields: Vec<(SchemaField, Option<FieldValue>)>,
+}
+
+impl Projection {
+    pub fn new(fields: Vec<SchemaField>) -> Result<Self, String> {
+        let fields = fields
+            .into_iter()
+            .map(|field| {
+                let default_value = if field.has_default {
+                    let default_value = _default_value(&field)
+                        .map_err(|err| format!("invalid default value of field: path={} {}", field.path, err))?;
+                    Some(default_value)
+                } else {
+                    None
+                };
+                Ok((field, default_value))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { fields })
+    }
+
+    pub fn project(&self, event: &CxxLogEvent) -> ProjectedEvent {
+        let mut errors = Vec::new();
+        let values = self
+            .fields
+            .iter()
+            .map(|(field, default_value)| {
+                let coerced = match event.value(&field.path) {
+                    None | Some(Value::Null) if field.required && default_value.is_none() => {
+                        Err("required field is missing".to_string())
+                    }
+                    None | Some(Value::Null) => Ok(None),
+                    Some(value) => coerce(value, field.field_type).map(Some),
+                };
+                coerced.unwrap_or_else(|message| {
+                    errors.push(CoercionError { path: field.path.clone(), message });
+                    None
+                })
+                .or_else(|| default_value.clone())
+                .unwrap_or_else(FieldValue::null)
+            })
+            .collect();
+        ProjectedEvent { values, errors }
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, BooleanBuilder, Float64Builder, Int64Builder, NullArray, StringBuilder,
+    StructArray, TimestampMicrosecondBuilder,
+};
+use arrow::compute::filter;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+use vector::event::Value;
+use crate::CxxLogEvent;
+
+type Row<'a> = HashMap<String, &'a Value>;
+
+/*
+The leaf values of an event by their dotted paths, the same as the ones returned by
+`CxxLogEvent::fields`, except that an array is kept as a single column of its JSON string.
+ */
+fn flatten<'a>(prefix: &str, object: &'a BTreeMap<String, Value>, row: &mut Vec<(String, &'a Value)>) {
+    for (key, value) in object {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match value {
+            Value::Object(object) if !object.is_empty() => flatten(&path, object, row),
+            _ => row.push((path, value)),
+        }
+    }
+}
+
+fn inferred_type(value: &Value) -> DataType {
+    match value {
+        Value::Integer(_) => DataType::Int64,
+        Value::Float(_) => DataType::Float64,
+        Value::Boolean(_) => DataType::Boolean,
+        Value::Timestamp(_) => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        Value::Null => DataType::Null,
+        _ => DataType::Utf8,
+    }
+}
+
+// a column of mixed types is widened to double for numbers, or string for the others
+fn merged_type(current: DataType, other: DataType) -> DataType {
+    match (current, other) {
+        (current, other) if current == other => current,
+        (DataType::Null, other) => other,
+        (current, DataType::Null) => current,
+        (DataType::Int64, DataType::Float64) | (DataType::Float64, DataType::Int64) => DataType::Float64,
+        _ => DataType::Utf8,
+    }
+}
+
+// columns are ordered by their first appearance in the events
+fn infer_schema(rows: &[Vec<(String, &Value)>]) -> Schema {
+    let mut fields: Vec<(String, DataType)> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        for (path, value) in row {
+            match indices.get(path) {
+                Some(index) => {
+                    let data_type = fields[*index].1.clone();
+                    fields[*index].1 = merged_type(data_type, inferred_type(value));
+                }
+                None => {
+                    indices.insert(path.clone(), fields.len());
+                    fields.push((path.clone(), inferred_type(value)));
+                }
+            }
+        }
+    }
+    Schema::new(
+        fields
+            .into_iter()
+            .map(|(path, data_type)| Field::new(path, data_type, true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn is_supported(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Utf8
+            | DataType::Int64
+            | DataType::Float64
+            | DataType::Boolean
+            | DataType::Timestamp(TimeUnit::Microsecond, _)
+            | DataType::Null
+    )
+}
+
+// a supplied schema is checked before any event is polled, so that it never fails on every batch
+pub fn check_schema(schema: &Schema) -> Result<(), String> {
+    match schema.fields().iter().find(|field| !is_supported(field.data_type())) {
+        Some(field) => Err(format!(
+            "unsupported data type of field: field={} data_type={}",
+            field.name(),
+            field.data_type()
+        )),
+        None => Ok(()),
+    }
+}
+
+// the values not convertible to the type of the field are null
+fn build_column(field: &Field, rows: &[Row]) -> Result<ArrayRef, String> {
+    let values = rows.iter().map(|row| row.get(field.name()).copied());
+    let column: ArrayRef = match field.data_type() {
+        DataType::Utf8 => {
+            let mut builder = StringBuilder::new();
+            values.for_each(|value| match value {
+                Some(Value::Bytes(bytes)) => builder.append_value(String::from_utf8_lossy(bytes)),
+                Some(Value::Null) | None => builder.append_null(),
+                Some(value) => builder.append_value(value.to_string_lossy()),
+            });
+            Arc::new(builder.finish())
+        }
+        DataType::Int64 => {
+            let mut builder = Int64Builder::new();
+            values.for_each(|value| builder.append_option(value.and_then(Value::as_integer)));
+            Arc::new(builder.finish())
+        }
+        DataType::Float64 => {
+            let mut builder = Float64Builder::new();
+            values.for_each(|value| match value {
+                Some(Value::Float(float)) => builder.append_value(float.into_inner()),
+                Some(Value::Integer(integer)) => builder.append_value(*integer as f64),
+                _ => builder.append_null(),
+            });
+            Arc::new(builder.finish())
+        }
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::new();
+            values.for_each(|value| builder.append_option(value.and_then(Value::as_boolean)));
+            Arc::new(builder.finish())
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            let mut builder = TimestampMicrosecondBuilder::new().with_data_type(field.data_type().clone());
+            values.for_each(|value| {
+                builder.append_option(value.and_then(Value::as_timestamp).map(|timestamp| timestamp.timestamp_micros()))
+            });
+            Arc::new(builder.finish())
+        }
+        DataType::Null => Arc::new(NullArray::new(rows.len())),
+        data_type => {
+            return Err(format!("unsupported data type of field: field={} data_type={}", field.name(), data_type));
+        }
+    };
+    Ok(column)
+}
+
+/*
+The schema is inferred from the events if it is not supplied. The rows with a null value in a
+non-nullable field are left out of the batch, and returned by their indices in `events` along
+with the reason, so that the other events of the batch are still exported.
+ */
+pub fn to_record_batch(
+    events: &[CxxLogEvent],
+    schema: Option<SchemaRef>,
+) -> Result<(RecordBatch, Vec<(usize, String)>), String> {
+    let flattened: Vec<Vec<(String, &Value)>> = events
+        .iter()
+        .map(|event| {
+            let mut row = Vec::new();
+            if let Some(object) = event.log_event.as_map() {
+                flatten("", object, &mut row);
+            }
+            row
+        })
+        .collect();
+    let schema = schema.unwrap_or_else(|| Arc::new(infer_schema(&flattened)));
+    let rows: Vec<Row> = flattened.into_iter().map(|row| row.into_iter().collect()).collect();
+    let columns = schema
+        .fields()
+        .iter()
+        .map(|field| build_column(field, &rows))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // only the first non-nullable field of a row with a null value is reported
+    let mut rejected: Vec<(usize, String)> = Vec::new();
+    let mut kept = vec![true; rows.len()];
+    for (field, column) in schema.fields().iter().zip(&columns) {
+        if field.is_nullable() || column.null_count() == 0 {
+            continue;
+        }
+        for index in (0..rows.len()).filter(|index| kept[*index] && column.is_null(*index)) {
+            kept[index] = false;
+            rejected.push((index, format!("null value in non-nullable field: field={}", field.name())));
+        }
+    }
+    rejected.sort_by_key(|(index, _)| *index);
+    let columns = if rejected.is_empty() {
+        columns
+    } else {
+        let mask = BooleanArray::from(kept);
+        columns
+            .iter()
+            .map(|column| filter(column, &mask))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())?
+    };
+    // the row count is required for a schema without any field
+    let options = RecordBatchOptions::new().with_row_count(Some(rows.len() - rejected.len()));
+    let batch = RecordBatch::try_new_with_options(schema, columns, &options).map_err(|err| err.to_string())?;
+    Ok((batch, rejected))
+}
+
+// the caller keeps the ownership of the schema
+pub unsafe fn import_schema(schema: *const FFI_ArrowSchema) -> Result<SchemaRef, String> {
+    Schema::try_from(&*schema)
+        .map(Arc::new)
+        .map_err(|err| format!("failed to import arrow schema: {}", err))
+}
+
+// the batch is exported as a struct array, the caller takes the ownership and releases them
+pub unsafe fn export_record_batch(
+    batch: RecordBatch,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> Result<(), String> {
+    let data = StructArray::from(batch).into_data();
+    let schema = FFI_ArrowSchema::try_from(data.data_type())
+        .map_err(|err| format!("failed to export arrow schema: {}", err))?;
+    std::ptr::write(out_schema, schema);
+    std::ptr::write(out_array, FFI_ArrowArray::new(&data));
+    Ok(())
+}
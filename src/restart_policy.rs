@@ -0,0 +1,48 @@
+use std::time::Duration;
+use crate::ffi::{ControllerError, RestartPolicy, RestartStatus};
+
+impl Default for RestartPolicy {
+    // restart is disabled by default
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 60000,
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn enabled(&self) -> bool {
+        self.max_retries > 0
+    }
+
+    // the backoff before each attempt of a restart, doubled after every failed attempt
+    pub fn backoffs(&self) -> impl Iterator<Item = Duration> {
+        let max_backoff_ms = self.max_backoff_ms.max(self.initial_backoff_ms);
+        let mut backoff_ms = self.initial_backoff_ms;
+        (0..self.max_retries).map(move |_| {
+            let backoff = Duration::from_millis(backoff_ms);
+            backoff_ms = backoff_ms.saturating_mul(2).min(max_backoff_ms);
+            backoff
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct RestartState {
+    pub policy: RestartPolicy,
+    pub status: RestartStatus,
+}
+
+impl Default for RestartStatus {
+    fn default() -> Self {
+        Self {
+            restarting: false,
+            attempts: 0,
+            restarts: 0,
+            gave_up: false,
+            last_error: ControllerError::none(),
+        }
+    }
+}
@@ -1,37 +1,142 @@
+use crate::async_operation::AsyncOperations;
+use crate::component_health::{is_controller_alive, ComponentHealthLayer, HealthMonitor, CONTROLLER_TARGET};
+use crate::component_info::ComponentGenerations;
 use crate::config_event::{ConfigAction, ConfigEvent};
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Once;
-use std::sync::{Arc, Mutex};
-use std::collections::HashMap;
-use tracing::{debug, error, info, Level};
-use vector::config::{ConfigBuilder, Config, ComponentKey, ConfigDiff, load_from_str};
+use crate::config_history::ConfigHistory;
+use crate::config_state::{self, ConfigState};
+use crate::config_graph::ConfigGraph;
+use crate::drain_stats::{self, SinkCountersSnapshot};
+use crate::restart_policy::RestartState;
+use crate::ffi::{
+    BatchConfigResult, ComponentInfo, ConfigDiffResult, ConfigHistoryEntry, ConfigOperation, ConfigResult,
+    ControllerError, ControllerResult, DeleteConfigResult, ErrorKind, HealthCallback, OperationCallback,
+    OperationStatus, RestartPolicy, RestartStatus, StopResult, TopologyStatus, ValidationResult,
+};
+use cxx::UniquePtr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, Weak};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::time::Duration;
+use metrics_tracing_context::MetricsLayer;
+use tracing::{debug, error, info, warn, Instrument, Level};
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+use vector::config::{ConfigBuilder, Config, ComponentKey, ConfigDiff};
+use tokio::sync::mpsc::UnboundedReceiver;
 use vector::topology::RunningTopology;
 use vector::{config, config::format, metrics, test_util::runtime};
 
+// all the states are shared, a clone is a handle to the same controller used by async operations
+#[derive(Clone)]
 pub struct TopologyController {
     generation_id: Arc<AtomicU32>,
-    topology: Arc<Mutex<Option<RunningTopology>>>,
+    topology: Arc<Mutex<Option<Topology>>>,
     config_builder: Arc<Mutex<Option<ConfigBuilder>>>,
+    component_generations: Arc<Mutex<ComponentGenerations>>,
+    config_history: Arc<Mutex<ConfigHistory>>,
+    // the committed config is persisted to this file if set
+    state_path: Arc<Mutex<Option<PathBuf>>>,
+    operations: Arc<AsyncOperations>,
+    // set once the controller exits, it could not be started again
+    exited: Arc<AtomicBool>,
+    health: Arc<HealthMonitor>,
+    restart: Arc<Mutex<RestartState>>,
     rt: Arc<tokio::runtime::Runtime>,
 }
 
+/*
+A running topology with the runtime its component tasks are spawned on. The running topology does
+not expose its tasks, so shutting down the runtime is the only way to abort them.
+ */
+struct Topology {
+    running: RunningTopology,
+    rt: tokio::runtime::Runtime,
+    // the sink counters before the sinks are started by this topology, see `drain_stats`
+    sink_baseline: SinkCountersSnapshot,
+}
+
+impl Topology {
+    // called before reloading, as the sinks added by the reload are not running yet
+    fn refresh_sink_baseline(&mut self, config_builder: &ConfigBuilder) {
+        let running_sink_ids: Vec<String> = config_builder.sinks.keys().map(|key| key.id().to_string()).collect();
+        self.sink_baseline.update_baseline(&running_sink_ids, SinkCountersSnapshot::capture_all());
+    }
+}
+
+// a handle not keeping the controller alive, used by the tasks running in background
+struct WeakTopologyController {
+    generation_id: Weak<AtomicU32>,
+    topology: Weak<Mutex<Option<Topology>>>,
+    config_builder: Weak<Mutex<Option<ConfigBuilder>>>,
+    component_generations: Weak<Mutex<ComponentGenerations>>,
+    config_history: Weak<Mutex<ConfigHistory>>,
+    state_path: Weak<Mutex<Option<PathBuf>>>,
+    operations: Weak<AsyncOperations>,
+    exited: Weak<AtomicBool>,
+    health: Weak<HealthMonitor>,
+    restart: Weak<Mutex<RestartState>>,
+    rt: Weak<tokio::runtime::Runtime>,
+}
+
+impl WeakTopologyController {
+    fn upgrade(&self) -> Option<TopologyController> {
+        Some(TopologyController {
+            generation_id: self.generation_id.upgrade()?,
+            topology: self.topology.upgrade()?,
+            config_builder: self.config_builder.upgrade()?,
+            component_generations: self.component_generations.upgrade()?,
+            config_history: self.config_history.upgrade()?,
+            state_path: self.state_path.upgrade()?,
+            operations: self.operations.upgrade()?,
+            exited: self.exited.upgrade()?,
+            health: self.health.upgrade()?,
+            restart: self.restart.upgrade()?,
+            rt: self.rt.upgrade()?,
+        })
+    }
+}
+
 pub struct OneShotTopologyController {
     rt: Arc<tokio::runtime::Runtime>,
 }
 
-static START: Once = Once::new();
-static INIT: Once = Once::new();
+// the timeout of stopping topology when the controller exits
+const EXIT_TIMEOUT: Duration = Duration::from_secs(30);
+// the timeout of stopping a crashed topology before restarting it
+const RESTART_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+// the timeout of waiting for the blocking tasks of the aborted components
+const ABORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the results of the process-wide initializations are kept, so every controller started after a
+// failed one is rejected with the same error instead of running without them
+static LOGGING: OnceLock<Result<(), ControllerError>> = OnceLock::new();
+static INIT: OnceLock<Result<(), ControllerError>> = OnceLock::new();
 
-pub fn setup_logging() {
+// it fails if the host process has installed its own global tracing subscriber
+pub fn setup_logging() -> Result<(), ControllerError> {
     let timer = tracing_subscriber::fmt::time::time();
-    let collector = tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
+    let fmt_layer = tracing_subscriber::fmt::layer()
         // disable color to make CLion happy
         .with_ansi(false)
         .with_thread_ids(true)
         .with_timer(timer)
-        .finish();
-    tracing::subscriber::set_global_default(collector).expect("setting default subscriber failed");
+        .with_filter(LevelFilter::INFO);
+    let collector = tracing_subscriber::registry()
+        .with(fmt_layer)
+        // capture the component id from tracing spans as tags of vector internal metrics
+        .with(MetricsLayer::new().with_filter(LevelFilter::INFO))
+        // the spans of the component tasks and the controllers spawning them
+        .with(ComponentHealthLayer.with_filter(
+            Targets::new()
+                .with_target("vector::topology", Level::DEBUG)
+                .with_target(CONTROLLER_TARGET, Level::INFO),
+        ));
+    tracing::subscriber::set_global_default(collector).map_err(|err| {
+        ControllerError::new(ErrorKind::InitFailed, "", format!("failed to set global tracing subscriber: {}", err))
+    })
 }
 
 fn _print_ids(config: &mut ConfigBuilder) {
@@ -52,39 +157,63 @@ fn _print_ids(config: &mut ConfigBuilder) {
     info!("sink ids: {:?}", sink_ids);
 }
 
-async fn _handle_reload(new: ConfigBuilder, old: &mut ConfigBuilder, topology: &mut RunningTopology) -> bool {
-    let new_copy = new.clone();
+async fn _reload_topology(config: Config, topology: &mut RunningTopology) -> Result<bool, ControllerError> {
     match topology
-        .reload_config_and_respawn(new.build().unwrap())
+        .reload_config_and_respawn(config)
         .await
     {
-        Ok(true) => {
-            info!("vector config reloaded succeed");
-            *old = new_copy;
-            _print_ids(old);
-        },
+        Ok(true) => Ok(true),
         Ok(false) => {
-            info!("vector config reloaded does not succeed");
-            return false;
+            info!("reload and respawn failed, restore old config");
+            Err(ControllerError::new(
+                ErrorKind::ReloadRejected,
+                "",
+                "reload and respawn failed, restored old config",
+            ))
         },
         Err(()) => {
-            error!("error happen while reloading config");
-            // TODO: handle error here
-            return false;
+            error!("error happen while reloading config, failed to restore old config");
+            Err(ControllerError::new(
+                ErrorKind::RollbackFailed,
+                "",
+                "reload and respawn failed, failed to restore old config",
+            ))
         }
     }
-    true
 }
 
-async fn reload_vector(
-    config_event: ConfigEvent,
-    config_builder: &mut ConfigBuilder,
+async fn _handle_reload(
+    controller_id: u64,
+    new: ConfigBuilder,
+    old: &mut ConfigBuilder,
     topology: &mut RunningTopology,
-) -> bool {
-    debug!("sources before {:?}: {:?}", config_event.action, config_builder.sources);
-    debug!("transforms before {:?}: {:?}", config_event.action, config_builder.transforms);
-    debug!("sinks before {:?}: {:?}", config_event.action,  config_builder.sinks);
+) -> Result<bool, ControllerError> {
+    _check_log_schema(&new)?;
+    _sync_memory_queue_owner(controller_id, Some(&new))?;
+    let new_copy = new.clone();
+    let reloaded = match new.build() {
+        Ok(config) => _reload_topology(config, topology).await,
+        Err(errors) => Err(ControllerError::config_build(errors)),
+    };
+    if let Err(err) = reloaded {
+        // the old config keeps running
+        _sync_memory_queue_owner(controller_id, Some(old))?;
+        return Err(err);
+    }
+    info!("vector config reloaded succeed");
+    *old = new_copy;
+    _print_ids(old);
+    Ok(true)
+}
 
+// apply a config event to a copy of the config builder, return the new config builder and the ids
+// of the components added, updated or deleted
+fn apply_config_event(
+    config_event: &ConfigEvent,
+    config_builder: &ConfigBuilder,
+) -> Result<(ConfigBuilder, Vec<String>), ControllerError> {
+    let mut config_builder_new = config_builder.clone();
+    let mut changed_ids = Vec::new();
     match config_event.action {
         ConfigAction::INIT => {
             // should not go here
@@ -93,93 +222,124 @@ async fn reload_vector(
             let config_str = &config_event.config_str;
             let new_builder: ConfigBuilder =
                 config::format::deserialize(config_str.as_str(), config::Format::Json)
-                    .unwrap();
-            let mut config_builder_new = config_builder.clone();
+                    .map_err(ControllerError::config_parse)?;
+            changed_ids = new_builder
+                .sources
+                .keys()
+                .chain(new_builder.transforms.keys())
+                .chain(new_builder.sinks.keys())
+                .map(|key| key.id().to_string())
+                .collect();
             if new_builder.sources.len() > 0 {
                 config_builder_new.sources.extend(new_builder.sources);
             }
             if new_builder.transforms.len() > 0 {
                 config_builder_new.transforms.extend(new_builder.transforms);
             }
-            debug!("sources after {:?}: {:?}", config_event.action, config_builder_new.sources);
-            debug!("transforms after {:?}: {:?}", config_event.action, config_builder_new.transforms);
-            debug!("sinks after {:?}: {:?}", config_event.action, config_builder_new.sinks);
-            if !_handle_reload(config_builder_new, config_builder, topology).await {
-                // TODO: handle error here
-                return false;
+            if new_builder.sinks.len() > 0 {
+                config_builder_new.sinks.extend(new_builder.sinks);
             }
         }
         ConfigAction::DELETE => {
-            // source and transform can not use same name in vector
-            let mut config_builder_new = config_builder.clone();
+            let graph = ConfigGraph::new(config_builder);
+            // ids not existing in current config are ignored
+            let mut deleted_ids: BTreeSet<String> = config_event
+                .config_ids
+                .iter()
+                .filter(|id| graph.contains(id))
+                .cloned()
+                .collect();
+            if config_event.cascade {
+                deleted_ids = graph.cascade(&deleted_ids);
+            } else {
+                let dangling = graph.dangling_inputs(&deleted_ids);
+                if let Some((consumer_id, _)) = dangling.first() {
+                    return Err(ControllerError::new(
+                        ErrorKind::DanglingInput,
+                        consumer_id,
+                        format!("components still take deleted components as input: {:?}", dangling),
+                    ));
+                }
+            }
 
-            for id in &config_event.config_ids {
+            // source, transform and sink can not use same name in vector
+            for id in &deleted_ids {
                 let key = &ComponentKey::from(id.clone());
                 if config_builder_new.sources.get(key).is_some() {
                     config_builder_new.sources.remove(key);
                 } else if config_builder_new.transforms.get(key).is_some() {
                     config_builder_new.transforms.remove(key);
+                } else if config_builder_new.sinks.get(key).is_some() {
+                    config_builder_new.sinks.remove(key);
                 }
             }
-            debug!("sources after {:?}: {:?}", config_event.action, config_builder_new.sources);
-            debug!("transforms after {:?}: {:?}", config_event.action, config_builder_new.transforms);
-            debug!("sinks after {:?}: {:?}", config_event.action, config_builder_new.sinks);
-            if !_handle_reload(config_builder_new, config_builder, topology).await {
-                // TODO: handle error here
-                return false;
-            }
+            changed_ids = deleted_ids.into_iter().collect();
         }
         ConfigAction::EXIT => {
             // should not go here
         }
     }
-    true
+    Ok((config_builder_new, changed_ids))
 }
 
-async fn reload_vector_from_str(config_str: &str, topology: &mut RunningTopology) -> Result<bool, String> {
-    match load_from_str(config_str, config::Format::Json) {
-        Ok(config) => {
-            info!("config str: {:?}", config_str);
-            match topology
-                .reload_config_and_respawn(config)
-                .await
-            {
-                Ok(true) => {
-                    info!("config reloaded succeed");
-                },
-                Ok(false) => {
-                    info!("reload and respawn failed, restore old config");
-                    return Err("reload and respawn failed, restored old config".to_string());
-                },
-                Err(()) => {
-                    return Err("reload and respawn failed, failed to restore old config".to_string());
-                }
+// apply config events in order and reload the topology once, so that either all of them take
+// effect or none of them. Return the ids of the components added, updated or deleted.
+async fn reload_vector(
+    controller_id: u64,
+    config_events: Vec<ConfigEvent>,
+    config_builder: &mut ConfigBuilder,
+    topology: &mut RunningTopology,
+) -> Result<Vec<String>, ControllerError> {
+    debug!("sources before: {:?}", config_builder.sources);
+    debug!("transforms before: {:?}", config_builder.transforms);
+    debug!("sinks before: {:?}", config_builder.sinks);
+
+    let mut config_builder_new = config_builder.clone();
+    let mut changed_ids = Vec::new();
+    let is_batch = config_events.len() > 1;
+    for (index, config_event) in config_events.iter().enumerate() {
+        let (builder, ids) = apply_config_event(config_event, &config_builder_new).map_err(|mut err| {
+            if is_batch {
+                err.message = format!("config event {} {}: {}", index, config_event.action, err.message);
             }
-        }
-        Err(err) => {
-            return Err(format!("reload from str error: {:?}", err));
-        }
+            err
+        })?;
+        config_builder_new = builder;
+        changed_ids.extend(ids);
     }
-    Ok(true)
+
+    debug!("sources after: {:?}", config_builder_new.sources);
+    debug!("transforms after: {:?}", config_builder_new.transforms);
+    debug!("sinks after: {:?}", config_builder_new.sinks);
+    _handle_reload(controller_id, config_builder_new, config_builder, topology).await?;
+    Ok(changed_ids)
 }
 
-fn advance_generation(result: bool, generation_id: &AtomicU32) -> bool {
-    if result {
+fn advance_generation(result: Result<bool, ControllerError>, generation_id: &AtomicU32) -> ControllerResult {
+    if result.is_ok() {
         generation_id.fetch_add(1, Ordering::Relaxed);
     }
-    result
+    ControllerResult::new(result, generation_id.load(Ordering::Relaxed))
 }
 
-pub fn init_config(config_str: &str) -> Result<ConfigBuilder, String> {
-    START.call_once(|| {
-        setup_logging();
-    });
-
-    let res = format::deserialize(config_str, config::Format::Json);
-    if res.is_err() {
-        return Err(format!("configuration error: {:?}", res.unwrap_err().join(",")));
+/*
+A full config is loaded by the vector config loader, which interpolates the `${ENV}` variables
+before deserializing it, the same as `load_from_str` did for reloading.
+ */
+fn load_config_builder(config_str: &str) -> Result<ConfigBuilder, ControllerError> {
+    let (config_builder, warnings): (ConfigBuilder, Vec<String>) =
+        config::load(config_str.as_bytes(), config::Format::Json).map_err(ControllerError::config_parse)?;
+    for warning in warnings {
+        warn!("config loading warning: {}", warning);
     }
-    let config_builder: ConfigBuilder = res.unwrap();
+    Ok(config_builder)
+}
+
+pub fn init_config(config_str: &str) -> Result<ConfigBuilder, ControllerError> {
+    LOGGING.get_or_init(setup_logging).clone()?;
+
+    let config_builder: ConfigBuilder = format::deserialize(config_str, config::Format::Json)
+        .map_err(ControllerError::config_parse)?;
     debug!(
         "config_builder deserialized; sources={:?} transforms={:?} sinks={:?} global={:?}",
         config_builder.sources,
@@ -187,90 +347,541 @@ pub fn init_config(config_str: &str) -> Result<ConfigBuilder, String> {
         config_builder.sinks,
         config_builder.global
     );
-    INIT.call_once(|| {
-        let builder_for_schema = config_builder.clone();
-        let _init_result = config::init_log_schema_from_builder(builder_for_schema, false).expect("init log schema failed");
+    INIT.get_or_init(|| {
+        config::init_log_schema_from_builder(config_builder.clone(), false).map_err(ControllerError::config_build)?;
         #[cfg(not(feature = "enterprise-tests"))]
-        metrics::init_global().expect("metrics initialization failed");
-    });
+        metrics::init_global().map_err(|err| {
+            ControllerError::new(ErrorKind::InitFailed, "", format!("failed to initialize metrics: {}", err))
+        })?;
+        Ok(())
+    })
+    .clone()?;
+    _check_log_schema(&config_builder)?;
 
     info!("config constructed via config builder");
     Ok(config_builder)
 }
 
+/*
+Log schema is global in vector and is initialized by the first config, controllers in the same
+process are able to run independently as long as they require exactly the same log schema. A
+default log schema is rejected too once a custom one is initialized, or the controller would run
+under the custom one silently.
+ */
+fn _check_log_schema(config_builder: &ConfigBuilder) -> Result<(), ControllerError> {
+    if &config_builder.global.log_schema != config::log_schema() {
+        return Err(ControllerError::new(
+            ErrorKind::ConfigBuild,
+            "",
+            "log_schema is different from the one initialized by another controller in the process",
+        ));
+    }
+    Ok(())
+}
+
+/*
+The memory queue sinks share the single global receiver taken by the memory queue clients, so
+only one controller in the process is able to run them, a memory queue sink of another controller
+would take over the receiver silently. This is only a guard until the memory_queue sink of the
+vector fork supports a queue per name, then each controller could consume its own queue. The
+vector internal metrics are process-wide too, the components of different controllers are told
+apart by their component ids only.
+ */
+static MEMORY_QUEUE_OWNER: Mutex<Option<u64>> = Mutex::new(None);
+
+fn _uses_memory_queue(config_builder: &ConfigBuilder) -> bool {
+    config_builder.sinks.values().any(|sink| {
+        let sink = serde_json::to_value(sink).unwrap_or_default();
+        sink["type"].as_str() == Some("memory_queue")
+    })
+}
+
+fn _check_memory_queue_free(controller_id: u64, owner: Option<u64>) -> Result<(), ControllerError> {
+    match owner {
+        Some(owner_id) if owner_id != controller_id && is_controller_alive(owner_id) => Err(ControllerError::new(
+            ErrorKind::ConfigBuild,
+            "",
+            "memory_queue sink is already used by another controller in the process",
+        )),
+        _ => Ok(()),
+    }
+}
+
+// check if a controller could claim the memory queue for a config without claiming it
+fn _check_memory_queue_owner(controller_id: u64, config_builder: &ConfigBuilder) -> Result<(), ControllerError> {
+    if !_uses_memory_queue(config_builder) {
+        return Ok(());
+    }
+    _check_memory_queue_free(controller_id, *MEMORY_QUEUE_OWNER.lock().unwrap())
+}
+
+// claim the memory queue for a controller if its config uses it, or release it otherwise
+fn _sync_memory_queue_owner(controller_id: u64, config_builder: Option<&ConfigBuilder>) -> Result<(), ControllerError> {
+    let mut owner = MEMORY_QUEUE_OWNER.lock().unwrap();
+    if config_builder.map_or(false, _uses_memory_queue) {
+        _check_memory_queue_free(controller_id, *owner)?;
+        *owner = Some(controller_id);
+    } else if *owner == Some(controller_id) {
+        *owner = None;
+    }
+    Ok(())
+}
+
+/*
+Building a component could have side effects on the process, like a kafka source joining its
+consumer group or a memory queue sink registering the global receiver taken by the clients, so
+only the component types built without side effects are built while validating. Their builds
+only compile VRL programs or create clients, and nothing is bound or registered. The other
+components are checked by building the config only, and reported in the warnings.
+ */
+const VALIDATION_BUILT_TRANSFORMS: &[&str] = &[
+    "remap", "route", "filter", "reduce", "dedupe", "sample", "throttle", "log_to_metric", "metric_to_log",
+];
+const VALIDATION_BUILT_SINKS: &[&str] = &[
+    "blackhole", "console", "file", "http", "elasticsearch", "clickhouse", "kafka", "loki", "splunk_hec_logs",
+];
+
+// a disk buffer is opened while building the sink, and it is locked by the running one
+fn _has_disk_buffer(sink: &serde_json::Value) -> bool {
+    let is_disk = |buffer: &serde_json::Value| buffer["type"].as_str().map_or(false, |type_| type_.starts_with("disk"));
+    match &sink["buffer"] {
+        serde_json::Value::Array(stages) => stages.iter().any(is_disk),
+        buffer => is_disk(buffer),
+    }
+}
+
+// the ids of the components which are not safe to be built, with their kinds and types
+fn _unbuilt_components(config_builder: &ConfigBuilder) -> Vec<(ComponentKey, &'static str, String)> {
+    let value = serde_json::to_value(config_builder).unwrap_or_default();
+    let type_of = |kind: &str, key: &ComponentKey| value[kind][key.id()]["type"].as_str().unwrap_or_default().to_string();
+    let sources = config_builder.sources.keys().map(|key| (key.clone(), "source", type_of("sources", key)));
+    let transforms = config_builder
+        .transforms
+        .keys()
+        .map(|key| (key.clone(), "transform", type_of("transforms", key)))
+        .filter(|(_, _, type_)| !VALIDATION_BUILT_TRANSFORMS.contains(&type_.as_str()));
+    let sinks = config_builder
+        .sinks
+        .keys()
+        .map(|key| (key.clone(), "sink", type_of("sinks", key)))
+        .filter(|(key, _, type_)| {
+            !VALIDATION_BUILT_SINKS.contains(&type_.as_str()) || _has_disk_buffer(&value["sinks"][key.id()])
+        });
+    sources.chain(transforms).chain(sinks).collect()
+}
+
+// build the config and the components safe to be built without running them, VRL programs of
+// remap transforms are compiled while building. Nothing is changed for the running topology.
+pub async fn validate_config(config_builder: ConfigBuilder, run_healthchecks: bool) -> ValidationResult {
+    let unbuilt = _unbuilt_components(&config_builder);
+    let (config, mut warnings) = match config_builder.build_with_warnings() {
+        Ok(res) => res,
+        Err(errors) => {
+            return ValidationResult::new(
+                errors.into_iter().map(|error| ControllerError::new(ErrorKind::ConfigBuild, "", error)).collect(),
+                Vec::new(),
+            )
+        }
+    };
+    let mut diff = ConfigDiff::initial(&config);
+    for (key, kind, type_) in unbuilt {
+        diff.sources.to_add.remove(&key);
+        diff.transforms.to_add.remove(&key);
+        diff.sinks.to_add.remove(&key);
+        warnings.push(format!("{} is not built while validating: component_id={} type={}", kind, key.id(), type_));
+    }
+    let mut pieces = match vector::topology::builder::build_pieces(&config, &diff, HashMap::new()).await {
+        Ok(pieces) => pieces,
+        Err(errors) => {
+            return ValidationResult::new(
+                errors.into_iter().map(|error| ControllerError::new(ErrorKind::ConfigBuild, "", error)).collect(),
+                warnings,
+            )
+        }
+    };
+
+    let mut errors = Vec::new();
+    if run_healthchecks {
+        if !config.healthchecks.enabled {
+            warnings.push("health checks are disabled".to_string());
+        }
+        // run health checks one by one so that the errors can be related to components
+        for (key, healthcheck) in vector::topology::take_healthchecks(&diff, &mut pieces) {
+            debug!("validating health check component_id={}", key.id());
+            match tokio::spawn(healthcheck).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(err)) => errors.push(ControllerError::new(
+                    ErrorKind::HealthCheck,
+                    key.id(),
+                    format!("health check failed: {:?}", err),
+                )),
+                Err(err) => errors.push(ControllerError::new(
+                    ErrorKind::HealthCheck,
+                    key.id(),
+                    format!("health check not completed: {}", err),
+                )),
+            }
+        }
+    }
+    ValidationResult::new(errors, warnings)
+}
+
 impl TopologyController {
     pub fn new() -> Self {
         Self {
             generation_id: Arc::new(AtomicU32::new(0)),
             topology: Arc::new(Mutex::new(None)),
             config_builder: Arc::new(Mutex::new(None)),
+            component_generations: Arc::new(Mutex::new(ComponentGenerations::default())),
+            config_history: Arc::new(Mutex::new(ConfigHistory::default())),
+            state_path: Arc::new(Mutex::new(None)),
+            operations: Arc::new(AsyncOperations::default()),
+            exited: Arc::new(AtomicBool::new(false)),
+            health: HealthMonitor::new(),
+            restart: Arc::new(Mutex::new(RestartState::default())),
             rt: Arc::new(runtime()),
         }
     }
 
     // run a topology with tokio runtime
-    pub fn start(&mut self, topology_config: &str) -> Result<bool, String> {
+    pub fn start(&mut self, topology_config: &str) -> ControllerResult {
+        let config_builder = match init_config(topology_config) {
+            Ok(config_builder) => config_builder,
+            Err(err) => return ControllerResult::new(Err(err), self.get_generation_id()),
+        };
+        info!("start vector service");
+
+        let res = self._start(config_builder);
+        self._advance_generation(res)
+    }
+
+    /*
+    Start the topology with the config persisted in the state file under `data_dir` of the given
+    config, and keep persisting the committed config of each generation. The given config is used
+    if there is no state file, or the persisted config fails to start.
+     */
+    pub fn start_with_persisted_config(&mut self, topology_config: &str) -> ControllerResult {
+        let config_builder = match init_config(topology_config) {
+            Ok(config_builder) => config_builder,
+            Err(err) => return ControllerResult::new(Err(err), self.get_generation_id()),
+        };
+        let state_path = match config_state::state_path(&config_builder) {
+            Some(state_path) => state_path,
+            None => {
+                let err = ControllerError::new(ErrorKind::ConfigBuild, "", "data_dir is required to persist config");
+                return ControllerResult::new(Err(err), self.get_generation_id());
+            }
+        };
+        *self.state_path.lock().unwrap() = Some(state_path.clone());
 
-        let builder = init_config(topology_config);
-        if builder.is_err() {
-            return Err(builder.unwrap_err());
+        let mut persisted_generation_id = None;
+        match ConfigState::load(&state_path) {
+            Ok(Some(state)) => {
+                info!("start vector service with persisted config generation_id={}", state.generation_id);
+                match self._start(state.config_builder) {
+                    Ok(_) => {
+                        // the persisted generation is restored as it is
+                        self.generation_id.store(state.generation_id, Ordering::Relaxed);
+                        self._record_generation();
+                        return ControllerResult::new(Ok(true), state.generation_id);
+                    }
+                    Err(err) => {
+                        error!("failed to start with persisted config, fall back to given config: error={}", err);
+                        persisted_generation_id = Some(state.generation_id);
+                    }
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                error!("failed to load persisted config, fall back to given config: error={}", err);
+            }
         }
+
         info!("start vector service");
+        let res = self._start(config_builder);
+        // the given config is committed as the generation next to the persisted one
+        if let (Ok(_), Some(generation_id)) = (&res, persisted_generation_id) {
+            self.generation_id.store(generation_id, Ordering::Relaxed);
+        }
+        self._advance_generation(res)
+    }
 
-        let config_builder = builder.unwrap();
-        *self.config_builder.lock().unwrap() = Some(config_builder.clone());
-        let topology_cp = self.topology.clone();
-        let config = config_builder.build().unwrap();
+    fn _start(&mut self, config_builder: ConfigBuilder) -> Result<bool, ControllerError> {
+        if self.exited.load(Ordering::Relaxed) {
+            return Err(ControllerError::new(ErrorKind::Exited, "", "controller is exited"));
+        }
+        // both locks are held until the topology is stored, so that a concurrent start sees it
+        // running, they are taken in the same order as reloading
+        let mut config_builder_slot = self.config_builder.lock().unwrap();
+        let mut topology_slot = self.topology.lock().unwrap();
+        if topology_slot.is_some() {
+            return Err(ControllerError::new(
+                ErrorKind::AlreadyStarted,
+                "",
+                "topology is already started, use reload instead",
+            ));
+        }
+        let config = config_builder.clone().build().map_err(ControllerError::config_build)?;
         info!("config constructed via config builder");
 
-        let rt = self.rt.clone();
-
-        rt.block_on(async {
-            let (topology, _crash) =
-                vector::test_util::start_topology(config, false).await;
-            info!("vector topology started");
-            *topology_cp.lock().unwrap() = Some(topology);
-            // no need to handle source_finished here
-            let mut _sources_finished = topology_cp.lock().unwrap().as_ref().unwrap().sources_finished();
-        });
-
-        info!("vector thread spawned");
-        advance_generation(true, &self.generation_id);
+        let controller_id = self.health.controller_id();
+        _sync_memory_queue_owner(controller_id, Some(&config_builder))?;
+        let sink_baseline = SinkCountersSnapshot::capture_all();
+        let rt = runtime();
+        let started = rt.block_on(start_topology_validated(config, false).instrument(self.health.spawn_span()));
+        let (running, crash) = started.map_err(|err| {
+            let _ = _sync_memory_queue_owner(controller_id, None);
+            err
+        })?;
+        info!("vector topology started");
+        self.health.set_started();
+        self._watch_crash(crash);
+        *topology_slot = Some(Topology { running, rt, sink_baseline });
+        // the config builder is only kept once the topology is running, so that incremental
+        // config events can not be applied to a topology failed to start
+        *config_builder_slot = Some(config_builder);
         Ok(true)
     }
 
-    pub fn add_config(&mut self, config: String) -> bool {
-        let res = self.rt.block_on(self.handle_config_event("add".to_string(), vec![], config));
-        advance_generation(res, &self.generation_id)
+    pub fn add_config(&mut self, config: String) -> ControllerResult {
+        let res = self.handle_config_event(ConfigAction::ADD, vec![], config, false);
+        self._advance_generation(res.map(|_| true))
+    }
+
+    pub fn delete_config(&mut self, config_ids: Vec<String>, cascade: bool) -> DeleteConfigResult {
+        let res = self.handle_config_event(ConfigAction::DELETE, config_ids, "".to_string(), cascade);
+        let removed_ids = res.as_ref().map_or_else(|_| Vec::new(), |ids| ids.clone());
+        DeleteConfigResult {
+            result: self._advance_generation(res.map(|_| true)),
+            removed_ids,
+        }
     }
 
-    pub fn delete_config(&mut self, config_ids: Vec<String>) -> bool {
-        let res = self.rt.block_on(self.handle_config_event("delete".to_string(), config_ids, "".to_string()));
-        advance_generation(res, &self.generation_id)
+    pub fn update_config(&mut self, config: String) -> ControllerResult {
+        let res = self.handle_config_event(ConfigAction::UPDATE, vec![], config, false);
+        self._advance_generation(res.map(|_| true))
     }
 
-    pub fn update_config(&mut self, config: String) -> bool {
-        let res = self.rt.block_on(self.handle_config_event("update".to_string(), vec![], config));
-        advance_generation(res, &self.generation_id)
+    // apply all the operations with a single reload as one generation, if any of them fails, none
+    // of them takes effect
+    pub fn apply_config_batch(&mut self, operations: Vec<ConfigOperation>) -> BatchConfigResult {
+        let res = operations
+            .into_iter()
+            .map(ConfigEvent::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|config_events| self.handle_config_events(config_events));
+        let changed_ids = res.as_ref().map_or_else(|_| Vec::new(), |ids| ids.clone());
+        BatchConfigResult {
+            result: self._advance_generation(res.map(|_| true)),
+            changed_ids,
+        }
     }
 
-    pub fn exit(&mut self) -> bool {
+    pub fn exit(&mut self) -> ControllerResult {
         // no need to handle config event, stop topology directly.
-        let res = self.stop();
-        advance_generation(res, &self.generation_id)
+        self.exited.store(true, Ordering::Relaxed);
+        self._cancel_restart();
+        let stop_result = self._stop(Some(EXIT_TIMEOUT));
+        // the committed config is released, it is still persisted if required
+        *self.config_builder.lock().unwrap() = None;
+        stop_result.result
+    }
+
+    pub fn stop(&mut self) -> ControllerResult {
+        self._cancel_restart();
+        self._stop(None).result
     }
 
-    pub fn stop(&mut self) -> bool {
+    pub fn stop_with_timeout(&mut self, timeout_ms: u64) -> StopResult {
+        self._cancel_restart();
+        self._stop(Some(Duration::from_millis(timeout_ms)))
+    }
+
+    /*
+    Stop the topology and wait for all the components to shut down, or until the timeout is reached.
+    Once timed out, the components not shut down yet are aborted with the runtime of the topology,
+    so their ports and files are released before returning.
+     */
+    fn _stop(&mut self, timeout: Option<Duration>) -> StopResult {
         // avoid double stop
-        if self.topology.lock().unwrap().as_ref().is_none() {
-            return true;
+        let topology = self.topology.lock().unwrap().take();
+        let Topology { running, rt, sink_baseline } = match topology {
+            Some(topology) => topology,
+            None => {
+                return StopResult {
+                    result: ControllerResult::new(Ok(true), self.get_generation_id()),
+                    graceful: true,
+                    sinks: Vec::new(),
+                }
+            }
+        };
+        // the component tasks exit while stopping, they are not reported as finished
+        self.health.set_stopping();
+        let sink_ids: Vec<String> = self
+            .config_builder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or_else(Vec::new, |config_builder| {
+                config_builder.sinks.keys().map(|key| key.id().to_string()).collect()
+            });
+        let counters_before = SinkCountersSnapshot::capture(&sink_ids);
+
+        let graceful = {
+            // here we need to enter runtime context explicitly, or there will be tokio timer panic in
+            // the topology stop method, it's weird.
+            let _guard = rt.enter();
+            rt.block_on(async move {
+                match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, running.stop()).await.is_ok(),
+                    None => {
+                        running.stop().await;
+                        true
+                    }
+                }
+            })
+        };
+        if !graceful {
+            warn!("topology is not stopped in time, aborting the components left: timeout={:?}", timeout);
+        }
+        // the component tasks still running are dropped, the blocking ones are signaled to shut
+        // down as their shutdown triggers are dropped with the stop future
+        rt.shutdown_timeout(ABORT_TIMEOUT);
+        self.health.set_stopped();
+        let _ = _sync_memory_queue_owner(self.health.controller_id(), None);
+
+        let counters_after = SinkCountersSnapshot::capture(&sink_ids);
+        StopResult {
+            result: ControllerResult::new(Ok(true), self.get_generation_id()),
+            graceful,
+            sinks: drain_stats::drain_stats(&sink_ids, &sink_baseline, &counters_before, &counters_after),
         }
-        // here we need to enter runtime context explicitly, or there will be tokio timer panic in
-        // the topology stop method, it's weird.
-        let _guard = self.rt.enter();
-        self.rt.block_on(self.topology.lock().unwrap().take().unwrap().stop());
-        *self.topology.lock().unwrap() = None;
-        true
+    }
+
+    fn _downgrade(&self) -> WeakTopologyController {
+        WeakTopologyController {
+            generation_id: Arc::downgrade(&self.generation_id),
+            topology: Arc::downgrade(&self.topology),
+            config_builder: Arc::downgrade(&self.config_builder),
+            component_generations: Arc::downgrade(&self.component_generations),
+            config_history: Arc::downgrade(&self.config_history),
+            state_path: Arc::downgrade(&self.state_path),
+            operations: Arc::downgrade(&self.operations),
+            exited: Arc::downgrade(&self.exited),
+            health: Arc::downgrade(&self.health),
+            restart: Arc::downgrade(&self.restart),
+            rt: Arc::downgrade(&self.rt),
+        }
+    }
+
+    // a crash is reported once any component task fails, the channel is closed once the topology stops
+    fn _watch_crash(&self, mut crash: UnboundedReceiver<()>) {
+        let health = self.health.clone();
+        let restart = self.restart.clone();
+        let operations = Arc::downgrade(&self.operations);
+        let controller = self._downgrade();
+        self.rt.spawn(async move {
+            if crash.recv().await.is_none() {
+                return;
+            }
+            error!("vector topology crashed");
+            health.set_crashed();
+            if !restart.lock().unwrap().policy.enabled() {
+                return;
+            }
+            // the controller is upgraded on the worker thread, so that it is never dropped inside the runtime
+            if let Some(operations) = operations.upgrade() {
+                restart.lock().unwrap().status.restarting = true;
+                operations.submit(Box::new(move || match controller.upgrade() {
+                    Some(mut controller) => controller._restart(),
+                    None => ControllerResult::new(Err(ControllerError::not_started()), 0),
+                }));
+            }
+        });
+    }
+
+    /*
+    Stop the crashed topology and start it again with the last committed config, as a new
+    generation. Each attempt is made after a backoff, until it succeeds or the retries run out.
+     */
+    fn _restart(&mut self) -> ControllerResult {
+        let policy = self.restart.lock().unwrap().policy.clone();
+        if !self._is_restarting() {
+            return ControllerResult::new(Ok(false), self.get_generation_id());
+        }
+        self._stop(Some(RESTART_STOP_TIMEOUT));
+        let config_builder = self.config_builder.lock().unwrap().clone();
+        let mut result = Err(ControllerError::not_started());
+        if let (Some(config_builder), false) = (config_builder, self.exited.load(Ordering::Relaxed)) {
+            let restart_state = self.restart.clone();
+            for backoff in policy.backoffs() {
+                std::thread::sleep(backoff);
+                // the restart lock is held until the attempt is made, so that a stop cancelling the
+                // restart either cancels the attempt, or waits for it and stops the topology started
+                let mut restart = restart_state.lock().unwrap();
+                if !restart.status.restarting {
+                    info!("restarting crashed topology is cancelled");
+                    return ControllerResult::new(Ok(false), self.get_generation_id());
+                }
+                restart.status.attempts += 1;
+                result = self._start(config_builder.clone());
+                drop(restart);
+                match &result {
+                    Ok(_) => break,
+                    Err(err) => error!("failed to restart crashed topology: backoff={:?} error={}", backoff, err),
+                }
+            }
+        }
+
+        let mut restart = self.restart.lock().unwrap();
+        restart.status.restarting = false;
+        restart.status.gave_up = result.is_err();
+        match &result {
+            Ok(_) => {
+                restart.status.restarts += 1;
+                info!("crashed topology restarted: attempts={}", restart.status.attempts);
+            }
+            Err(err) => restart.status.last_error = err.clone(),
+        }
+        drop(restart);
+        self._advance_generation(result)
+    }
+
+    fn _is_restarting(&self) -> bool {
+        self.restart.lock().unwrap().status.restarting
+    }
+
+    // stopping the topology explicitly cancels the pending restart, it waits for the restart attempt
+    // in progress if any
+    fn _cancel_restart(&self) {
+        self.restart.lock().unwrap().status.restarting = false;
+    }
+
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) {
+        self.restart.lock().unwrap().policy = policy;
+    }
+
+    pub fn get_restart_status(&self) -> RestartStatus {
+        self.restart.lock().unwrap().status.clone()
+    }
+
+    fn _watch_sources_finished(&self, generation_id: u32) {
+        if let Some(topology) = self.topology.lock().unwrap().as_ref() {
+            let sources_finished = topology.running.sources_finished();
+            let health = self.health.clone();
+            topology.rt.spawn(async move {
+                sources_finished.await;
+                health.set_sources_finished(generation_id);
+            });
+        }
+    }
+
+    pub fn get_topology_status(&self) -> TopologyStatus {
+        self.health.status()
+    }
+
+    pub fn set_health_callback(&mut self, callback: UniquePtr<HealthCallback>) {
+        self.health.set_callback(callback);
     }
 
     // a self increment id to indicate which generation of config is currently running
@@ -278,36 +889,249 @@ impl TopologyController {
         self.generation_id.load(Ordering::Relaxed)
     }
 
+    fn _advance_generation(&self, res: Result<bool, ControllerError>) -> ControllerResult {
+        let result = advance_generation(res, &self.generation_id);
+        if result.succeed {
+            self._record_generation();
+        }
+        result
+    }
+
+    // record the current config as committed in current generation
+    fn _record_generation(&self) {
+        let generation_id = self.get_generation_id();
+        if let Some(config_builder) = self.config_builder.lock().unwrap().as_ref() {
+            let mut component_generations = self.component_generations.lock().unwrap();
+            component_generations.update(config_builder, generation_id);
+            self.health
+                .commit_generation(generation_id, component_generations.component_infos(config_builder));
+            self._watch_sources_finished(generation_id);
+            self.config_history
+                .lock()
+                .unwrap()
+                .record(generation_id, config_builder.clone());
+            if let Some(state_path) = self.state_path.lock().unwrap().as_ref() {
+                // failing to persist config does not fail the committed generation
+                if let Err(err) = ConfigState::save(state_path, generation_id, config_builder) {
+                    error!("failed to persist config: generation_id={} error={}", generation_id, err);
+                }
+            }
+        }
+    }
+
+    // return the committed configs kept in history, ordered by generation id
+    pub fn get_config_history(&self) -> Vec<ConfigHistoryEntry> {
+        self.config_history
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(generation_id, config_builder)| ConfigHistoryEntry {
+                generation_id: *generation_id,
+                config: serde_json::to_string(config_builder).unwrap_or_default(),
+            })
+            .collect()
+    }
 
-    async fn handle_config_event(&self, action: String, ids: Vec<String>, config_str: String) -> bool {
-        let get_action = |action| match action {
-            "init" => ConfigAction::INIT,
-            "add" => ConfigAction::ADD,
-            "update" => ConfigAction::UPDATE,
-            "delete" => ConfigAction::DELETE,
-            "exit" => ConfigAction::EXIT,
-            _ => ConfigAction::INIT,
+    // the oldest configs are dropped if there are more than `max_size` configs in history
+    pub fn set_max_config_history(&mut self, max_size: usize) {
+        self.config_history.lock().unwrap().set_max_size(max_size);
+    }
+
+    // reload the config committed in an earlier generation, the rollback is committed as a new
+    // generation
+    pub fn rollback_to(&mut self, generation_id: u32) -> ControllerResult {
+        let config_builder = self.config_history.lock().unwrap().get(generation_id).cloned();
+        let res = match config_builder {
+            Some(config_builder) => {
+                info!("rollback config generation_id={}", generation_id);
+                self._reload(config_builder)
+            }
+            None => Err(ControllerError::new(
+                ErrorKind::GenerationNotFound,
+                "",
+                format!("generation {} is not found in config history", generation_id),
+            )),
         };
-        info!(
-            "about to handle vector config event action={:?}",
-            action.as_str()
-        );
-        debug!(
-            "handling config event: action={:?} config={:?}",
-            action.as_str(),
-            config_str
-        );
+        self._advance_generation(res)
+    }
+
+    // reload the topology with a full config
+    fn _reload(&self, config_builder_new: ConfigBuilder) -> Result<bool, ControllerError> {
+        let mut config_builder = self.config_builder.lock().unwrap();
+        let mut topology = self.topology.lock().unwrap();
+        match (config_builder.as_mut(), topology.as_mut()) {
+            (Some(config_builder), Some(topology)) => {
+                topology.refresh_sink_baseline(config_builder);
+                let reload =
+                    _handle_reload(self.health.controller_id(), config_builder_new, config_builder, &mut topology.running);
+                topology.rt.block_on(reload.instrument(self.health.spawn_span()))
+            }
+            _ => Err(ControllerError::not_started()),
+        }
+    }
+
+    // return all the components of current config, empty if the topology is not started
+    pub fn get_components(&self) -> Vec<ComponentInfo> {
+        match self.config_builder.lock().unwrap().as_ref() {
+            Some(config_builder) => self.component_generations.lock().unwrap().component_infos(config_builder),
+            None => Vec::new(),
+        }
+    }
+
+    // return the current effective config serialized as json
+    pub fn get_config(&self) -> ConfigResult {
+        let res = self
+            .config_builder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .ok_or_else(ControllerError::not_started)
+            .and_then(|config_builder| {
+                serde_json::to_string(config_builder)
+                    .map_err(|err| ControllerError::new(ErrorKind::ConfigParse, "", err.to_string()))
+            });
+        ConfigResult::new(res, self.get_generation_id())
+    }
+
 
-        reload_vector(ConfigEvent {
-            action: get_action(action.as_str()),
+    fn handle_config_event(&self, action: ConfigAction, ids: Vec<String>, config_str: String, cascade: bool) -> Result<Vec<String>, ControllerError> {
+        self.handle_config_events(vec![ConfigEvent {
+            action,
             config_ids: ids,
             config_str,
-        }, self.config_builder.lock().unwrap().as_mut().unwrap(), self.topology.lock().unwrap().as_mut().unwrap()).await
+            cascade,
+        }])
     }
 
-    pub fn handle_config_reload(&self, config_str: &str) -> Result<bool, String> {
-        let res = self.rt.block_on(reload_vector_from_str(config_str, self.topology.lock().unwrap().as_mut().unwrap()));
-        res
+    // the components are reloaded on the runtime of the topology, so they could be aborted with it
+    fn handle_config_events(&self, config_events: Vec<ConfigEvent>) -> Result<Vec<String>, ControllerError> {
+        info!(
+            "about to handle vector config events actions={:?}",
+            config_events.iter().map(|event| event.action.to_string()).collect::<Vec<_>>()
+        );
+        debug!("handling config events: events={:?}", config_events);
+
+        let mut config_builder = self.config_builder.lock().unwrap();
+        let mut topology = self.topology.lock().unwrap();
+        match (config_builder.as_mut(), topology.as_mut()) {
+            (Some(config_builder), Some(topology)) => {
+                topology.refresh_sink_baseline(config_builder);
+                let reload =
+                    reload_vector(self.health.controller_id(), config_events, config_builder, &mut topology.running);
+                topology.rt.block_on(reload.instrument(self.health.spawn_span()))
+            }
+            _ => Err(ControllerError::not_started()),
+        }
+    }
+
+    // validate a full config as if it is used to start or reload the topology
+    pub fn validate_config(&self, config_str: &str, run_healthchecks: bool) -> ValidationResult {
+        match load_config_builder(config_str).and_then(|config_builder| self._check_reloadable(config_builder)) {
+            Ok(config_builder) => self.rt.block_on(validate_config(config_builder, run_healthchecks)),
+            Err(err) => ValidationResult::new(vec![err], Vec::new()),
+        }
+    }
+
+    // validate an incremental config operation against the current config
+    pub fn validate_config_operation(&self, operation: ConfigOperation, run_healthchecks: bool) -> ValidationResult {
+        let res = self
+            ._apply_config_operation(operation)
+            .and_then(|(config_builder, _)| self._check_reloadable(config_builder));
+        match res {
+            Ok(config_builder) => self.rt.block_on(validate_config(config_builder, run_healthchecks)),
+            Err(err) => ValidationResult::new(vec![err], Vec::new()),
+        }
+    }
+
+    // preview what would be changed in the running topology by reloading a full config
+    pub fn diff_config(&self, config_str: &str) -> ConfigDiffResult {
+        let res = load_config_builder(config_str)
+            .and_then(|config_builder| self._check_reloadable(config_builder))
+            .and_then(|config_builder| self._diff_config(config_builder));
+        ConfigDiffResult::new(res, self.get_generation_id())
+    }
+
+    // preview what would be changed in the running topology by an incremental config operation
+    pub fn diff_config_operation(&self, operation: ConfigOperation) -> ConfigDiffResult {
+        let res = self
+            ._apply_config_operation(operation)
+            .and_then(|(config_builder, _)| self._check_reloadable(config_builder))
+            .and_then(|config_builder| self._diff_config(config_builder));
+        ConfigDiffResult::new(res, self.get_generation_id())
+    }
+
+    // the checks made by reloading besides building the config, so that a config validated or diffed
+    // without errors is not rejected by reloading, the log schema is only fixed once initialized
+    fn _check_reloadable(&self, config_builder: ConfigBuilder) -> Result<ConfigBuilder, ControllerError> {
+        if INIT.get().is_some() {
+            _check_log_schema(&config_builder)?;
+        }
+        _check_memory_queue_owner(self.health.controller_id(), &config_builder)?;
+        Ok(config_builder)
+    }
+
+    // apply an operation to a copy of the current config, the current config is not changed
+    fn _apply_config_operation(&self, operation: ConfigOperation) -> Result<(ConfigBuilder, Vec<String>), ControllerError> {
+        // the config builder is cloned so that the lock is not held after applied
+        let config_builder = self.config_builder.lock().unwrap().clone();
+        config_builder
+            .ok_or_else(ControllerError::not_started)
+            .and_then(|config_builder| apply_config_event(&ConfigEvent::try_from(operation)?, &config_builder))
+    }
+
+    fn _diff_config(&self, config_builder_new: ConfigBuilder) -> Result<(ConfigDiff, ConfigBuilder), ControllerError> {
+        let config_builder = self
+            .config_builder
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(ControllerError::not_started)?;
+        // the current config is running, so it is always able to be built
+        let config = config_builder.build().map_err(ControllerError::config_build)?;
+        let config_new = config_builder_new.clone().build().map_err(ControllerError::config_build)?;
+        Ok((ConfigDiff::new(&config, &config_new), config_builder_new))
+    }
+
+    pub fn handle_config_reload(&self, config_str: &str) -> ControllerResult {
+        let res = load_config_builder(config_str)
+            .and_then(|config_builder| {
+                info!("config str: {:?}", config_str);
+                self._reload(config_builder)
+            });
+        self._advance_generation(res)
+    }
+
+    fn _submit(&self, operation: impl FnOnce(&mut TopologyController) -> ControllerResult + Send + 'static) -> u64 {
+        let mut controller = self.clone();
+        self.operations.submit(Box::new(move || operation(&mut controller)))
+    }
+
+    pub fn start_async(&mut self, topology_config: String) -> u64 {
+        self._submit(move |controller| controller.start(&topology_config))
+    }
+
+    pub fn add_config_async(&mut self, config: String) -> u64 {
+        self._submit(move |controller| controller.add_config(config))
+    }
+
+    pub fn update_config_async(&mut self, config: String) -> u64 {
+        self._submit(move |controller| controller.update_config(config))
+    }
+
+    pub fn delete_config_async(&mut self, config_ids: Vec<String>, cascade: bool) -> u64 {
+        self._submit(move |controller| controller.delete_config(config_ids, cascade).result)
+    }
+
+    pub fn handle_config_reload_async(&mut self, config: String) -> u64 {
+        self._submit(move |controller| controller.handle_config_reload(&config))
+    }
+
+    pub fn get_operation_status(&self, operation_id: u64) -> OperationStatus {
+        self.operations.status(operation_id)
+    }
+
+    pub fn set_operation_callback(&mut self, callback: UniquePtr<OperationCallback>) {
+        self.operations.set_callback(callback);
     }
 }
 
@@ -319,45 +1143,52 @@ impl OneShotTopologyController {
     }
 
     // run topology and return after finished, no need to maintain datas for long run
-    pub fn start(&mut self, config_str: &str) -> Result<bool, String> {
-        let config_builder = init_config(config_str);
-        if config_builder.is_err() {
-            return Err(config_builder.unwrap_err());
-        }
+    pub fn start(&mut self, config_str: &str) -> ControllerResult {
+        let config_builder = match init_config(config_str) {
+            Ok(config_builder) => config_builder,
+            Err(err) => return ControllerResult::new(Err(err), 0),
+        };
         info!("start one time vector topology");
 
-        let config = config_builder.unwrap().build().unwrap();
+        let config = match config_builder.build() {
+            Ok(config) => config,
+            Err(errors) => return ControllerResult::new(Err(ControllerError::config_build(errors)), 0),
+        };
         info!("config constructed via config builder");
 
-        let (res, err) = self.rt.block_on(async {
-            let (res, err) = start_topology_sync(config, true).await;
-            (res, err)
-        });
-        if res {
-            Ok(true)
-        } else {
-            Err(err)
-        }
+        let res = self.rt.block_on(start_topology_sync(config, true));
+        // one shot topology has no generation
+        ControllerResult::new(res, 0)
     }
 }
 
-// this function start topology and waiting for source finished
-pub async fn start_topology_sync(
+// same as `vector::test_util::start_topology` but returns an error instead of panicking
+pub async fn start_topology_validated(
     mut config: Config,
     require_healthy: impl Into<Option<bool>>,
-) -> (bool, String) {
+) -> Result<(RunningTopology, UnboundedReceiver<()>), ControllerError> {
     config.healthchecks.set_require_healthy(require_healthy);
     let diff = ConfigDiff::initial(&config);
+    // the detailed build errors are logged by vector
     let pieces = vector::topology::build_or_log_errors(&config, &diff, HashMap::new())
         .await
-        .unwrap();
-    let result = vector::topology::start_validated(config, diff, pieces).await;
-    if result.is_none() {
-        return (false, "health check for sink failed".to_string());
-    }
+        .ok_or_else(|| {
+            ControllerError::new(ErrorKind::ConfigBuild, "", "failed to build topology components")
+        })?;
+    vector::topology::start_validated(config, diff, pieces)
+        .await
+        .ok_or_else(|| {
+            ControllerError::new(ErrorKind::HealthCheck, "", "health check for sink failed")
+        })
+}
 
-    let (topology, _crash) = result.unwrap();
+// this function start topology and waiting for source finished
+pub async fn start_topology_sync(
+    config: Config,
+    require_healthy: impl Into<Option<bool>>,
+) -> Result<bool, ControllerError> {
+    let (topology, _crash) = start_topology_validated(config, require_healthy).await?;
     topology.sources_finished().await;
     topology.stop().await;
-    (true, String::new())
+    Ok(true)
 }
@@ -0,0 +1,36 @@
+#pragma once
+
+#include <functional>
+#include <utility>
+
+// this header is included by the generated bridge header, so the bridge types could only be forward declared here
+namespace vectorcxx {
+  struct OperationStatus;
+  struct TopologyStatus;
+
+  // invoked on the controller worker thread when an async operation completes, the callback should be thread safe
+  // and must not register another callback to the controller
+  class OperationCallback {
+  public:
+    explicit OperationCallback(std::function<void(const OperationStatus &)> callback)
+        : callback_(std::move(callback)) {}
+
+    void on_completed(const OperationStatus &status) const { callback_(status); }
+
+  private:
+    std::function<void(const OperationStatus &)> callback_;
+  };
+
+  // invoked when the health status of the topology changes, on a dedicated notifier thread of the controller, so
+  // the callback is able to call the controller like getting the components, but must not register another callback
+  class HealthCallback {
+  public:
+    explicit HealthCallback(std::function<void(const TopologyStatus &)> callback)
+        : callback_(std::move(callback)) {}
+
+    void on_status_changed(const TopologyStatus &status) const { callback_(status); }
+
+  private:
+    std::function<void(const TopologyStatus &)> callback_;
+  };
+}
@@ -0,0 +1,63 @@
+#include <catch2/catch_test_macros.hpp>
+#include <catch2/generators/catch_generators.hpp>
+
+#include "vectorcxx_bridge/lib.h"
+#include <chrono>
+#include <spdlog/spdlog.h>
+#include <utility>
+
+namespace {
+  struct PollBenchmark {
+    uint64_t events = 0;
+    double seconds = 0;
+    uint64_t allocations = 0;
+    uint64_t allocated_bytes = 0;
+  };
+
+  // only the polling is measured, the events are generated into the queue before that
+  PollBenchmark benchmark_poll(size_t events_count, size_t event_len, size_t batch_size, bool is_json, bool cloned) {
+    auto memory_queue_client = vectorcxx::new_memory_queue_client_with_random_events(
+      events_count, events_count, event_len, batch_size, is_json);
+    PollBenchmark result;
+    auto stats_before = vectorcxx::get_allocation_stats();
+    auto started = std::chrono::steady_clock::now();
+    rust::Vec<vectorcxx::CxxLogEvent> events;
+    do {
+      events = cloned ? memory_queue_client->poll_cloned() : memory_queue_client->poll();
+      result.events += events.size();
+    } while (!events.empty());
+    result.seconds = std::chrono::duration<double>(std::chrono::steady_clock::now() - started).count();
+    auto stats_after = vectorcxx::get_allocation_stats();
+    result.allocations = stats_after.allocations - stats_before.allocations;
+    result.allocated_bytes = stats_after.allocated_bytes - stats_before.allocated_bytes;
+    return result;
+  }
+}
+
+// hidden from the default run, run it with `vector-tests "[benchmark]"` in a release build, the
+// allocations are only counted if it is configured with `-DVECTORCXX_ALLOC_STATS=ON`. The events
+// moved out by `poll` are compared with the ones cloned by `poll_cloned`, the polling before.
+TEST_CASE("benchmark polling random generated events", "[.][benchmark]") {
+  auto is_json = GENERATE(false, true);
+  size_t event_len = GENERATE(100, 1000);
+  size_t events_count = 100000;
+  size_t batch_size = 1000;
+  auto moved = benchmark_poll(events_count, event_len, batch_size, is_json, false);
+  auto cloned = benchmark_poll(events_count, event_len, batch_size, is_json, true);
+  REQUIRE(moved.events == events_count);
+  REQUIRE(cloned.events == events_count);
+  for (const auto &[path, result] : {std::pair{"moved", moved}, std::pair{"cloned", cloned}}) {
+    if (!vectorcxx::get_allocation_stats().enabled) {
+      spdlog::info("poll benchmark path={} is_json={} event_len={} events={} batch_size={} events_per_sec={:.0f}",
+                   path, is_json, event_len, result.events, batch_size, result.events / result.seconds);
+      continue;
+    }
+    spdlog::info("poll benchmark path={} is_json={} event_len={} events={} batch_size={} events_per_sec={:.0f} "
+                 "allocations_per_event={:.2f} allocated_bytes_per_event={:.0f}",
+                 path, is_json, event_len, result.events, batch_size, result.events / result.seconds,
+                 static_cast<double>(result.allocations) / result.events,
+                 static_cast<double>(result.allocated_bytes) / result.events);
+  }
+  spdlog::info("poll benchmark is_json={} event_len={} speedup={:.2f}", is_json, event_len,
+               cloned.seconds / moved.seconds);
+}
@@ -2,12 +2,16 @@
 #include <catch2/matchers/catch_matchers_vector.hpp>
 
 #include "vector_test_helper.h"
+#include "vectorcxx/arrow_c_data_interface.h"
 #include "vectorcxx/cxx_memory_queue_client.h"
+#include <chrono>
+#include <cstring>
 #include <nlohmann/json.hpp>
 
 using Catch::Matchers::VectorContains;
 using vectorcxx::CxxMemoryQueueClient;
 using vectorcxx::TopologyController;
+using vectorcxx::test::load_config;
 using vectorcxx::test::run;
 using vectorcxx::test::send_http_events;
 using nlohmann::json;
@@ -390,4 +394,290 @@ TEST_CASE("test fields and top level fields") {
       REQUIRE_THAT(field_vec, VectorContains(std::string("_message")));
     }
   });
-}
\ No newline at end of file
+}
+
+TEST_CASE("validating config does not take over running memory queue") {
+  run("http_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->validate_config(load_config("http_to_memory_queue"), true);
+    REQUIRE(result.valid);
+    std::vector<std::string> warnings(result.warnings.begin(), result.warnings.end());
+    REQUIRE_THAT(warnings, VectorContains(std::string(
+        "sink is not built while validating: component_id=sink_memory_queue type=memory_queue")));
+    REQUIRE_THAT(warnings, VectorContains(std::string(
+        "source is not built while validating: component_id=source_http type=http_server")));
+
+    send_http_events({"e0"});
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    auto events = memory_queue_client->poll_timeout(5000);
+    REQUIRE(events.size() == 1);
+    REQUIRE(events[0].get_string("message") == "e0");
+  });
+}
+
+TEST_CASE("poll events with timeout") {
+  run("http_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    auto started = std::chrono::steady_clock::now();
+    auto events = memory_queue_client->poll_timeout(200);
+    REQUIRE(events.empty());
+    REQUIRE(std::chrono::steady_clock::now() - started >= std::chrono::milliseconds(200));
+    REQUIRE(!memory_queue_client->is_closed());
+
+    send_http_events({"e0"});
+    events = memory_queue_client->poll_timeout(5000);
+    REQUIRE(events.size() == 1);
+    REQUIRE(events[0].get_string("message") == "e0");
+  });
+}
+
+TEST_CASE("poll batches coalesced up to max events") {
+  auto events_total = 1000;
+  auto memory_queue_client = vectorcxx::new_memory_queue_client_with_random_events(
+    2000, events_total, 100, 10, false);
+  uint32_t events_got = 0;
+  rust::Vec<vectorcxx::CxxLogEvent> events;
+  do {
+    events = memory_queue_client->poll_batch(256, 0, 0);
+    REQUIRE(events.size() <= 256);
+    if (events_got + 256 <= events_total) {
+      REQUIRE(events.size() == 256);
+    }
+    events_got += events.size();
+  } while (!events.empty());
+  REQUIRE(events_got == events_total);
+}
+
+TEST_CASE("poll batches coalesced up to max bytes") {
+  auto memory_queue_client = vectorcxx::new_memory_queue_client_with_random_events(
+    2000, 100, 100, 10, false);
+  auto events = memory_queue_client->poll_batch(100, 1, 0);
+  // a single event larger than the limit is still returned
+  REQUIRE(events.size() == 1);
+  auto rest = memory_queue_client->poll_batch(100, 0, 0);
+  REQUIRE(rest.size() == 99);
+}
+
+TEST_CASE("poll batch waits for events") {
+  run("http_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    send_http_events({"e0", "e1", "e2"});
+    // events are sent in separate requests, so they arrive in separate batches
+    auto events = memory_queue_client->poll_batch(3, 0, 5000);
+    REQUIRE(events.size() == 3);
+    REQUIRE(events[0].get_string("message") == "e0");
+    REQUIRE(events[2].get_string("message") == "e2");
+  });
+}
+
+namespace {
+  // the index of a column in the struct array exported by `poll_record_batch`, -1 if not found
+  int64_t find_column(const ArrowSchema &schema, const std::string &name) {
+    for (int64_t i = 0; i < schema.n_children; i++) {
+      if (name == schema.children[i]->name) {
+        return i;
+      }
+    }
+    return -1;
+  }
+
+  std::string string_value(const ArrowArray &column, int64_t row) {
+    auto offsets = static_cast<const int32_t *>(column.buffers[1]);
+    auto data = static_cast<const char *>(column.buffers[2]);
+    auto index = column.offset + row;
+    return {data + offsets[index], static_cast<size_t>(offsets[index + 1] - offsets[index])};
+  }
+
+  // a struct schema of a single field, owned by the test and never released by the consumer
+  struct SingleFieldSchema {
+    ArrowSchema field{};
+    ArrowSchema *children[1] = {&field};
+    ArrowSchema schema{};
+
+    SingleFieldSchema(const char *name, const char *format, int64_t flags) {
+      auto release = [](ArrowSchema *released) { released->release = nullptr; };
+      field.format = format;
+      field.name = name;
+      field.flags = flags;
+      field.release = release;
+      schema.format = "+s";
+      schema.name = "";
+      schema.n_children = 1;
+      schema.children = children;
+      schema.release = release;
+    }
+  };
+}
+
+TEST_CASE("export polled events as arrow record batch") {
+  run("http_json_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    send_http_events({R"({"a": 1, "b": {"c": "x"}})", R"({"a": 2.5, "b": {"c": "y"}})"});
+
+    ArrowArray array;
+    ArrowSchema schema;
+    auto exported = memory_queue_client->poll_record_batch(2, 0, 5000, nullptr, &array, &schema);
+    REQUIRE(exported.rows == 2);
+    REQUIRE(exported.rejected.empty());
+    REQUIRE(array.length == 2);
+    REQUIRE(std::strcmp(schema.format, "+s") == 0);
+
+    // integers and doubles of the same field are widened to doubles
+    auto a = find_column(schema, "a");
+    REQUIRE(a >= 0);
+    REQUIRE(std::strcmp(schema.children[a]->format, "g") == 0);
+    auto a_values = static_cast<const double *>(array.children[a]->buffers[1]);
+    REQUIRE(a_values[array.children[a]->offset] == 1.0);
+    REQUIRE(a_values[array.children[a]->offset + 1] == 2.5);
+
+    // nested objects are flattened into dotted paths
+    auto b_c = find_column(schema, "b.c");
+    REQUIRE(b_c >= 0);
+    REQUIRE(std::strcmp(schema.children[b_c]->format, "u") == 0);
+    REQUIRE(string_value(*array.children[b_c], 0) == "x");
+    REQUIRE(string_value(*array.children[b_c], 1) == "y");
+    array.release(&array);
+
+    // the values not convertible to the supplied schema are null
+    send_http_events({R"({"a": "not a number", "b": {"c": "z"}})"});
+    ArrowArray next_array;
+    ArrowSchema next_schema;
+    exported = memory_queue_client->poll_record_batch(1, 0, 5000, &schema, &next_array, &next_schema);
+    REQUIRE(exported.rows == 1);
+    REQUIRE(next_schema.n_children == schema.n_children);
+    REQUIRE(next_array.children[a]->null_count == 1);
+    REQUIRE(string_value(*next_array.children[b_c], 0) == "z");
+    next_array.release(&next_array);
+    next_schema.release(&next_schema);
+    schema.release(&schema);
+  });
+}
+
+TEST_CASE("leave unconvertible events out of arrow record batch") {
+  run("http_json_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    send_http_events({R"({"a": "e0"})", R"({"a": "e1", "b": "x"})"});
+    ArrowArray array;
+    ArrowSchema schema;
+
+    // an unsupported field type is rejected before any event is polled
+    SingleFieldSchema unsupported("a", "i", ARROW_FLAG_NULLABLE);
+    REQUIRE_THROWS(memory_queue_client->poll_record_batch(2, 0, 5000, &unsupported.schema, &array, &schema));
+
+    // only the event missing a non-nullable field is left out, and it is not polled again
+    SingleFieldSchema required("b", "u", 0);
+    auto exported = memory_queue_client->poll_record_batch(2, 0, 5000, &required.schema, &array, &schema);
+    REQUIRE(exported.rows == 1);
+    REQUIRE(array.length == 1);
+    REQUIRE(string_value(*array.children[0], 0) == "x");
+    REQUIRE(exported.rejected.size() == 1);
+    REQUIRE(exported.rejected[0].get_string("a") == "e0");
+    REQUIRE(exported.rejected_errors[0] == "null value in non-nullable field: field=b");
+    array.release(&array);
+    schema.release(&schema);
+
+    send_http_events({R"({"a": 3})"});
+    exported = memory_queue_client->poll_record_batch(1, 0, 5000, nullptr, &array, &schema);
+    REQUIRE(exported.rows == 1);
+    auto a = find_column(schema, "a");
+    REQUIRE(a >= 0);
+    auto a_values = static_cast<const int64_t *>(array.children[a]->buffers[1]);
+    REQUIRE(a_values[array.children[a]->offset] == 3);
+    array.release(&array);
+    schema.release(&schema);
+  });
+}
+
+TEST_CASE("project polled events into schema") {
+  run("http_json_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    rust::Vec<vectorcxx::SchemaField> fields;
+    fields.push_back({"id", vectorcxx::FieldType::Integer, true, false, ""});
+    fields.push_back({"score", vectorcxx::FieldType::Double, false, false, ""});
+    fields.push_back({"tags.host", vectorcxx::FieldType::String, false, false, ""});
+    fields.push_back({"ok", vectorcxx::FieldType::Boolean, false, true, "false"});
+    // the default values of objects and arrays are given in JSON
+    fields.push_back({"labels", vectorcxx::FieldType::Object, false, true, R"({"env": "dev"})"});
+    fields.push_back({"hosts", vectorcxx::FieldType::Array, false, true, "[]"});
+    REQUIRE_THROWS_AS(memory_queue_client->poll_projected(1, 0, 0), rust::Error);
+    auto invalid_fields = fields;
+    invalid_fields.push_back({"count", vectorcxx::FieldType::Integer, false, true, "many"});
+    REQUIRE_THROWS_AS(memory_queue_client->set_schema(invalid_fields), rust::Error);
+    invalid_fields = fields;
+    invalid_fields.push_back({"extra", vectorcxx::FieldType::Object, false, true, "[1]"});
+    REQUIRE_THROWS_AS(memory_queue_client->set_schema(invalid_fields), rust::Error);
+    invalid_fields = fields;
+    invalid_fields.push_back({"extra", vectorcxx::FieldType::Array, false, true, "not json"});
+    REQUIRE_THROWS_AS(memory_queue_client->set_schema(invalid_fields), rust::Error);
+    memory_queue_client->set_schema(fields);
+
+    send_http_events({R"({"id": "42", "score": 3, "tags": {"host": "h0"}, "ok": "true"})",
+                      R"({"score": "bad"})"});
+    auto events = memory_queue_client->poll_projected(2, 0, 5000);
+    REQUIRE(events.size() == 2);
+
+    auto &first = events[0];
+    REQUIRE(first.errors.empty());
+    REQUIRE(first.values[0].integer_value == 42);
+    REQUIRE(first.values[1].double_value == 3.0);
+    REQUIRE(first.values[2].string_value == "h0");
+    REQUIRE(first.values[3].boolean_value);
+    REQUIRE(first.values[4].string_value == R"({"env":"dev"})");
+    REQUIRE(first.values[5].string_value == "[]");
+
+    // the missing required field and the value failed to be coerced are both listed
+    auto &second = events[1];
+    REQUIRE(second.errors.size() == 2);
+    REQUIRE(second.errors[0].path == "id");
+    REQUIRE(second.errors[1].path == "score");
+    REQUIRE(second.values[0].is_null);
+    REQUIRE(second.values[1].is_null);
+    REQUIRE(second.values[2].is_null);
+    REQUIRE(!second.values[3].is_null);
+    REQUIRE(!second.values[3].boolean_value);
+  });
+}
+
+TEST_CASE("get values without panicking on missing keys or wrong types") {
+  run("http_json_to_memory_queue", [](rust::Box<TopologyController> &tc) {
+    send_http_events({R"({"count": 42, "ratio": "0.5", "text_count": "42", "flag": true,
+                          "at": "2023-01-02T03:04:05Z", "obj": {"k": "v"}, "arr": [1, 2]})"});
+    auto &memory_queue_client = CxxMemoryQueueClient::get_instance();
+    auto events = memory_queue_client->poll_timeout(5000);
+    REQUIRE(events.size() == 1);
+    auto &event = events[0];
+
+    int64_t integer = -1;
+    REQUIRE(event.try_get_integer("count", integer) == vectorcxx::ValueStatus::Present);
+    REQUIRE(integer == 42);
+    integer = -1;
+    REQUIRE(event.try_get_integer("missing", integer) == vectorcxx::ValueStatus::Absent);
+    REQUIRE(event.try_get_integer("text_count", integer) == vectorcxx::ValueStatus::WrongType);
+    REQUIRE(integer == -1);
+    bool flag = false;
+    REQUIRE(event.try_get_boolean("flag", flag) == vectorcxx::ValueStatus::Present);
+    REQUIRE(flag);
+    double ratio = 0;
+    REQUIRE(event.try_get_double("ratio", ratio) == vectorcxx::ValueStatus::WrongType);
+    int64_t timestamp = 0;
+    REQUIRE(event.try_get_timestamp("at", timestamp) == vectorcxx::ValueStatus::WrongType);
+    rust::String text;
+    REQUIRE(event.try_get_object_as_string("obj", text) == vectorcxx::ValueStatus::Present);
+    REQUIRE(text == R"({"k":"v"})");
+    REQUIRE(event.try_get_array_as_string("obj", text) == vectorcxx::ValueStatus::WrongType);
+    REQUIRE(event.try_get_array_as_string("arr", text) == vectorcxx::ValueStatus::Present);
+    REQUIRE(text == "[1,2]");
+
+    REQUIRE(event.coerce_integer("text_count", integer) == vectorcxx::ValueStatus::Present);
+    REQUIRE(integer == 42);
+    REQUIRE(event.coerce_double("count", ratio) == vectorcxx::ValueStatus::Present);
+    REQUIRE(ratio == 42.0);
+    REQUIRE(event.coerce_double("ratio", ratio) == vectorcxx::ValueStatus::Present);
+    REQUIRE(ratio == 0.5);
+    REQUIRE(event.coerce_timestamp("at", timestamp) == vectorcxx::ValueStatus::Present);
+    REQUIRE(timestamp == 1672628645000000);
+    REQUIRE(event.coerce_string("count", text) == vectorcxx::ValueStatus::Present);
+    REQUIRE(text == "42");
+    REQUIRE(event.coerce_boolean("obj", flag) == vectorcxx::ValueStatus::WrongType);
+    REQUIRE(event.coerce_integer("missing", integer) == vectorcxx::ValueStatus::Absent);
+  });
+}
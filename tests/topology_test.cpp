@@ -5,9 +5,17 @@
 
 #include "vector_test_helper.h"
 #include <exception>
+#include <functional>
+#include <fstream>
+#include <nlohmann/json.hpp>
 #include <regex>
 #include <string>
+#include <thread>
 #include <iostream>
+#include <atomic>
+#include <cstdlib>
+#include <mutex>
+#include <vector>
 
 using Catch::Matchers::ContainsSubstring;
 using vectorcxx::test::run;
@@ -16,8 +24,20 @@ using vectorcxx::test::send_http_events;
 using vectorcxx::test::read_events_from_sink;
 using vectorcxx::test::load_config;
 using vectorcxx::test::wait;
+using vectorcxx::test::SECOND_FILE_SINK_PATH;
+using vectorcxx::test::setup;
+using vectorcxx::test::wait_for_operation;
 using vectorcxx::TopologyController;
 using vectorcxx::OneShotTopologyController;
+using vectorcxx::ErrorKind;
+using vectorcxx::ConfigOperation;
+using vectorcxx::OperationState;
+using vectorcxx::OperationStatus;
+using vectorcxx::OperationCallback;
+using vectorcxx::ComponentHealth;
+using vectorcxx::HealthCallback;
+using vectorcxx::TopologyStatus;
+using vectorcxx::RestartPolicy;
 
 TEST_CASE("start single event http to file topology") {
   run("http_to_file",
@@ -70,7 +90,10 @@ TEST_CASE("update existing source in topology") {
 TEST_CASE("delete transform from topology") {
   run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
     tc->add_config(load_config("transform/add_field"));
-    tc->delete_config({"transform_remap_field"});
+    auto result = tc->delete_config({"transform_remap_field"}, false);
+    REQUIRE(result.result.succeed);
+    REQUIRE(result.removed_ids.size() == 1);
+    REQUIRE(std::string(result.removed_ids[0]) == "transform_remap_field");
     send_http_events({"e0", "e1"});
   });
   auto events = read_events_from_sink();
@@ -85,6 +108,84 @@ TEST_CASE("delete transform from topology") {
   REQUIRE_THAT(events[1], ContainsSubstring("42"));
 }
 
+TEST_CASE("add new sink to topology") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("sink/file")).succeed);
+    send_http_events({"hello", "world"});
+  });
+  REQUIRE(read_events_from_sink().size() == 2);
+  REQUIRE(read_events_from_sink(SECOND_FILE_SINK_PATH).size() == 2);
+}
+
+TEST_CASE("update existing sink in topology") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto config = load_config("sink/file");
+    REQUIRE(tc->add_config(config).succeed);
+    config = std::regex_replace(config, std::regex("\"json\""), "\"text\"");
+    REQUIRE(tc->update_config(config).succeed);
+    send_http_events({"hello"});
+  });
+  auto events = read_events_from_sink(SECOND_FILE_SINK_PATH);
+  REQUIRE(events.size() == 1);
+  REQUIRE(events[0] == "hello");
+}
+
+TEST_CASE("delete sink from topology") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("sink/file")).succeed);
+    send_http_events({"hello"});
+    REQUIRE(tc->delete_config({"sink_file_2"}, false).result.succeed);
+    send_http_events({"world"});
+  });
+  REQUIRE(read_events_from_sink().size() == 2);
+  REQUIRE(read_events_from_sink(SECOND_FILE_SINK_PATH).size() == 1);
+}
+
+TEST_CASE("delete source still used as input in topology") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("source_with_transform/http_with_transform")).succeed);
+    auto result = tc->delete_config({"source_http_1"}, false);
+    REQUIRE(!result.result.succeed);
+    REQUIRE(result.result.error.kind == ErrorKind::DanglingInput);
+    REQUIRE(std::string(result.result.error.component_id) == "transform_add_field_1");
+    REQUIRE(result.removed_ids.empty());
+    REQUIRE(tc->get_generation_id() == 2);
+  });
+}
+
+TEST_CASE("delete source matched by wildcard input in topology") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    // `transform_remap_field` takes `source_*` as input, which only matches `source_http`
+    auto result = tc->delete_config({"source_http"}, false);
+    REQUIRE(!result.result.succeed);
+    REQUIRE(result.result.error.kind == ErrorKind::DanglingInput);
+    REQUIRE(std::string(result.result.error.component_id) == "transform_remap_field");
+
+    // the wildcard still matches the new source
+    REQUIRE(tc->add_config(load_config("source_with_transform/http_with_transform")).succeed);
+    REQUIRE(tc->delete_config({"source_http"}, false).result.succeed);
+    send_http_events({"hello"}, 9998);
+  });
+  REQUIRE(read_events_from_sink().size() == 2);
+}
+
+TEST_CASE("cascade delete source and its dependent components in topology") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("source_with_transform/http_with_transform")).succeed);
+    auto result = tc->delete_config({"source_http_1", "not_exist"}, true);
+    REQUIRE(result.result.succeed);
+    REQUIRE(result.result.generation_id == 3);
+
+    std::vector<std::string> removed_ids;
+    for (auto const &id : result.removed_ids) {
+      removed_ids.emplace_back(id);
+    }
+    REQUIRE(removed_ids == std::vector<std::string>{"source_http_1", "transform_add_field_1"});
+    send_http_events({"hello"});
+  });
+  REQUIRE(read_events_from_sink().size() == 1);
+}
+
 // test if a new adding config impacts the previous added config
 TEST_CASE("add two transform from topology") {
   run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
@@ -115,24 +216,27 @@ TEST_CASE("get generation id") {
   run("file_to_file", [](rust::Box<TopologyController> &tc) {
     REQUIRE(tc->get_generation_id() == 1);
     auto config = load_config("source/http");
-    tc->add_config(config);
+    auto result = tc->add_config(config);
+    REQUIRE(result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::None);
+    REQUIRE(result.generation_id == 2);
     REQUIRE(tc->get_generation_id() == 2);
   });
 }
 
 TEST_CASE("test one shot topology") {
-  run_one_shot("batch_file_to_file", [](rust::Box<OneShotTopologyController> &tc) {});
+  auto result = run_one_shot("batch_file_to_file", [](rust::Box<OneShotTopologyController> &tc) {});
+  REQUIRE(result.succeed);
   auto events = read_events_from_sink();
   REQUIRE(events.size() == 2);
 }
 
 // test a kafka sink which is not started, which will fail on health check
 TEST_CASE("test one shot topology with sink not healthy") {
-  try {
-    run_one_shot("batch_file_to_kafka", [](rust::Box<OneShotTopologyController> &tc) {});
-  } catch (std::exception &e) {
-    REQUIRE(strcmp(e.what(), "health check for sink failed") == 0);
-  }
+  auto result = run_one_shot("batch_file_to_kafka", [](rust::Box<OneShotTopologyController> &tc) {});
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::HealthCheck);
+  REQUIRE(std::string(result.error.message) == "health check for sink failed");
 }
 
 TEST_CASE("run vector service with one time topology") {
@@ -143,7 +247,7 @@ TEST_CASE("run vector service with one time topology") {
     // add a one time topology
     auto config = load_config("batch_file_to_file");
     auto tc_one_shot = vectorcxx::new_one_shot_topology_controller();
-    REQUIRE(tc_one_shot->start(config));
+    REQUIRE(tc_one_shot->start(config).succeed);
 
     // update the long run service config
     uint32_t new_port = 8888;
@@ -156,12 +260,10 @@ TEST_CASE("run vector service with one time topology") {
 }
 
 TEST_CASE("test one shot topology with invalid source config") {
-  try {
-    run_one_shot("file_to_file_invalid", [](rust::Box<OneShotTopologyController> &tc) {});
-  } catch (std::exception &e) {
-    std::cout << "error: " << e.what() << std::endl;
-    REQUIRE_THAT(e.what(), ContainsSubstring("configuration error: \"unknown variant `beginnings`"));
-  }
+  auto result = run_one_shot("file_to_file_invalid", [](rust::Box<OneShotTopologyController> &tc) {});
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigParse);
+  REQUIRE_THAT(std::string(result.error.message), ContainsSubstring("unknown variant `beginnings`"));
 }
 
 TEST_CASE("test reload vector from valid config string can proceed") {
@@ -202,7 +304,7 @@ TEST_CASE("test reload vector from valid config string can proceed") {
       send_http_events({"hello"}); 
       // reload with config str
       auto config_str = rust::String(config_string);
-      REQUIRE(tc->handle_config_reload(config_str));
+      REQUIRE(tc->handle_config_reload(config_str).succeed);
       send_http_events({"hello", "world"});
   });
   auto events = read_events_from_sink();
@@ -241,7 +343,9 @@ TEST_CASE("test reload vector from invalid config string should fallback to exis
       send_http_events({"hello"}); 
       // reload with config str
       auto config_str = rust::String(config_string);
-      REQUIRE_THROWS(tc->handle_config_reload(config_str));
+      auto result = tc->handle_config_reload(config_str);
+      REQUIRE(!result.succeed);
+      REQUIRE(result.error.kind == ErrorKind::ConfigParse);
       send_http_events({"hello", "world"});
   });
   auto events = read_events_from_sink();
@@ -290,7 +394,7 @@ TEST_CASE("test reload vector from config string with health check") {
       send_http_events({"hello"}); 
       // reload with config str
       auto config_str = rust::String(config_string);
-      REQUIRE_THROWS(tc->handle_config_reload(config_str));
+      REQUIRE(!tc->handle_config_reload(config_str).succeed);
       send_http_events({"hello", "world"});
   });
   auto events = read_events_from_sink();
@@ -298,4 +402,834 @@ TEST_CASE("test reload vector from config string with health check") {
   REQUIRE_THAT(events[0], !ContainsSubstring(R"("age")"));
   REQUIRE_THAT(events[1], !ContainsSubstring(R"("age")"));
   REQUIRE_THAT(events[2], !ContainsSubstring(R"("age")"));
-}
\ No newline at end of file
+}
+
+TEST_CASE("add malformed json config to topology") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->add_config("{\"sources\": {");
+    REQUIRE(!result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::ConfigParse);
+    REQUIRE(result.generation_id == 1);
+    REQUIRE(tc->get_generation_id() == 1);
+
+    // the running topology is not impacted
+    REQUIRE(tc->add_config(load_config("source/http")).succeed);
+    send_http_events({"hello", "world"});
+  });
+  auto events = read_events_from_sink();
+  REQUIRE(events.size() == 2);
+}
+
+TEST_CASE("add config with unknown component type to topology") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->add_config(load_config("source/invalid_type"));
+    REQUIRE(!result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::ConfigParse);
+    REQUIRE(tc->get_generation_id() == 1);
+  });
+}
+
+TEST_CASE("add config failed to build to topology") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->add_config(load_config("transform/missing_input"));
+    REQUIRE(!result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::ConfigBuild);
+    REQUIRE_THAT(std::string(result.error.message), ContainsSubstring("source_not_exist"));
+    REQUIRE(tc->get_generation_id() == 1);
+  });
+}
+
+TEST_CASE("update and delete config with invalid input") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->update_config("not a json").error.kind == ErrorKind::ConfigParse);
+    REQUIRE(tc->update_config(load_config("transform/missing_input")).error.kind ==
+            ErrorKind::ConfigBuild);
+    REQUIRE(tc->handle_config_reload("not a json").error.kind == ErrorKind::ConfigParse);
+    REQUIRE(tc->get_generation_id() == 1);
+  });
+}
+
+TEST_CASE("call topology controller before start") {
+  auto tc = vectorcxx::new_topology_controller();
+  auto config = load_config("source/http");
+
+  auto result = tc->add_config(config);
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::NotStarted);
+  REQUIRE(tc->update_config(config).error.kind == ErrorKind::NotStarted);
+  REQUIRE(tc->delete_config({"source_http"}, false).result.error.kind == ErrorKind::NotStarted);
+  REQUIRE(tc->handle_config_reload(config).error.kind == ErrorKind::NotStarted);
+  REQUIRE(tc->get_generation_id() == 0);
+
+  // stopping a topology never started is a no-op
+  REQUIRE(tc->stop().succeed);
+}
+
+TEST_CASE("start topology controller with invalid config") {
+  auto tc = vectorcxx::new_topology_controller();
+  auto result = tc->start("{\"sources\": {");
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigParse);
+  REQUIRE(tc->get_generation_id() == 0);
+  // incremental config can not be applied to a topology failed to start
+  REQUIRE(tc->add_config(load_config("source/http")).error.kind == ErrorKind::NotStarted);
+}
+
+TEST_CASE("start topology controller twice") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->start(load_config("file_to_file"));
+    REQUIRE(!result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::AlreadyStarted);
+    REQUIRE(tc->get_generation_id() == 1);
+  });
+}
+
+TEST_CASE("concurrent sync and async starts run one topology") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  auto operation_id = tc->start_async(load_config("file_to_file"));
+  auto sync_result = tc->start(load_config("file_to_file"));
+  auto async_status = wait_for_operation(tc, operation_id, 10000);
+  REQUIRE(async_status.state == OperationState::Completed);
+  // exactly one of them starts the topology, the other one sees it started
+  REQUIRE(sync_result.succeed != async_status.result.succeed);
+  auto const &failed = sync_result.succeed ? async_status.result : sync_result;
+  REQUIRE(failed.error.kind == ErrorKind::AlreadyStarted);
+  REQUIRE(tc->get_generation_id() == 1);
+  REQUIRE(tc->stop().succeed);
+}
+
+TEST_CASE("call topology controller after stop") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->stop().succeed);
+    REQUIRE(tc->add_config(load_config("source/http")).error.kind == ErrorKind::NotStarted);
+    REQUIRE(tc->delete_config({"source_file"}, false).result.error.kind == ErrorKind::NotStarted);
+    // double stop is allowed
+    REQUIRE(tc->stop().succeed);
+  });
+}
+
+TEST_CASE("apply config batch as one generation") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    rust::Vec<ConfigOperation> operations;
+    operations.push_back(ConfigOperation{"add", load_config("source_with_transform/http_with_transform"), {}, false});
+    operations.push_back(ConfigOperation{"delete", "", {"source_http", "transform_remap_field"}, false});
+    operations.push_back(ConfigOperation{"add", load_config("transform/add_field"), {}, false});
+
+    auto result = tc->apply_config_batch(std::move(operations));
+    REQUIRE(result.result.succeed);
+    REQUIRE(result.result.generation_id == 2);
+    REQUIRE(tc->get_generation_id() == 2);
+    REQUIRE(result.changed_ids.size() == 5);
+    send_http_events({"e0"}, 9998);
+  });
+  auto events = read_events_from_sink();
+  // one from `transform_add_field_1` and one from `transform_add_field`
+  REQUIRE(events.size() == 2);
+  REQUIRE_THAT(events[0], !ContainsSubstring("my_source"));
+  REQUIRE_THAT(events[1], !ContainsSubstring("my_source"));
+}
+
+TEST_CASE("apply config batch with invalid operation rolls back all operations") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    rust::Vec<ConfigOperation> operations;
+    operations.push_back(ConfigOperation{"add", load_config("transform/add_field"), {}, false});
+    operations.push_back(ConfigOperation{"add", load_config("source/invalid_type"), {}, false});
+
+    auto result = tc->apply_config_batch(std::move(operations));
+    REQUIRE(!result.result.succeed);
+    REQUIRE(result.result.error.kind == ErrorKind::ConfigParse);
+    REQUIRE_THAT(std::string(result.result.error.message), ContainsSubstring("config event 1 ADD"));
+    REQUIRE(result.changed_ids.empty());
+    REQUIRE(tc->get_generation_id() == 1);
+    send_http_events({"e0"});
+  });
+  auto events = read_events_from_sink();
+  REQUIRE(events.size() == 1);
+  // the transform added in the first operation does not take effect
+  REQUIRE_THAT(events[0], !ContainsSubstring("42"));
+}
+
+TEST_CASE("config operation with unknown action is rejected") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    rust::Vec<ConfigOperation> operations;
+    operations.push_back(ConfigOperation{"add", load_config("transform/add_field"), {}, false});
+    operations.push_back(ConfigOperation{"remove", "", {"transform_remap_field"}, false});
+    auto result = tc->apply_config_batch(std::move(operations));
+    REQUIRE(!result.result.succeed);
+    REQUIRE(result.result.error.kind == ErrorKind::ConfigParse);
+    REQUIRE_THAT(std::string(result.result.error.message), ContainsSubstring("remove"));
+    REQUIRE(tc->get_generation_id() == 1);
+
+    auto validation = tc->validate_config_operation(ConfigOperation{"init", "", {}, false}, false);
+    REQUIRE(!validation.valid);
+    REQUIRE(validation.errors[0].kind == ErrorKind::ConfigParse);
+
+    auto diff = tc->diff_config_operation(ConfigOperation{"", "", {}, false});
+    REQUIRE(!diff.result.succeed);
+    REQUIRE(diff.result.error.kind == ErrorKind::ConfigParse);
+  });
+}
+
+TEST_CASE("validate full config without running topology") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->validate_config(load_config("http_to_file_with_transform"), false);
+    REQUIRE(result.valid);
+    REQUIRE(result.errors.empty());
+
+    result = tc->validate_config("{\"sources\": {", false);
+    REQUIRE(!result.valid);
+    REQUIRE(result.errors.size() == 1);
+    REQUIRE(result.errors[0].kind == ErrorKind::ConfigParse);
+
+    // kafka is not available in testing environment
+    result = tc->validate_config(load_config("batch_file_to_kafka"), true);
+    REQUIRE(!result.valid);
+    REQUIRE(result.errors[0].kind == ErrorKind::HealthCheck);
+    REQUIRE(std::string(result.errors[0].component_id) == "sink_kafka");
+
+    // the running topology is not changed
+    REQUIRE(tc->get_generation_id() == 1);
+    send_http_events({"hello"});
+  });
+  auto events = read_events_from_sink();
+  REQUIRE(events.size() == 1);
+  REQUIRE_THAT(events[0], !ContainsSubstring("my_source"));
+}
+
+TEST_CASE("validate config operation against current config") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->validate_config_operation(
+        ConfigOperation{"add", load_config("transform/add_field"), {}, false}, false);
+    REQUIRE(result.valid);
+
+    // VRL program is compiled while validating
+    result = tc->validate_config_operation(
+        ConfigOperation{"add", load_config("transform/invalid_vrl"), {}, false}, false);
+    REQUIRE(!result.valid);
+    REQUIRE(result.errors[0].kind == ErrorKind::ConfigBuild);
+    REQUIRE_THAT(std::string(result.errors[0].message), ContainsSubstring("transform_invalid_vrl"));
+
+    result = tc->validate_config_operation(ConfigOperation{"delete", "", {"source_http"}, false}, false);
+    REQUIRE(!result.valid);
+    REQUIRE(result.errors[0].kind == ErrorKind::DanglingInput);
+
+    REQUIRE(tc->get_generation_id() == 1);
+  });
+}
+
+TEST_CASE("validate config operation before start") {
+  auto tc = vectorcxx::new_topology_controller();
+  auto result = tc->validate_config_operation(
+      ConfigOperation{"add", load_config("source/http"), {}, false}, false);
+  REQUIRE(!result.valid);
+  REQUIRE(result.errors[0].kind == ErrorKind::NotStarted);
+}
+
+TEST_CASE("preview config diff of config operation") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    auto diff = tc->diff_config_operation(
+        ConfigOperation{"add", load_config("transform/add_field"), {}, false});
+    REQUIRE(diff.result.succeed);
+    REQUIRE(diff.transforms.to_add.size() == 1);
+    REQUIRE(std::string(diff.transforms.to_add[0]) == "transform_add_field");
+    REQUIRE(diff.transforms.unchanged.size() == 1);
+    REQUIRE(std::string(diff.transforms.unchanged[0]) == "transform_remap_field");
+    REQUIRE(diff.sources.to_add.empty());
+    REQUIRE(diff.sources.to_change.empty());
+    REQUIRE(diff.sources.unchanged.size() == 1);
+
+    REQUIRE(tc->add_config(load_config("source_with_transform/http_with_transform")).succeed);
+    diff = tc->diff_config_operation(ConfigOperation{"delete", "", {"source_http_1"}, true});
+    REQUIRE(diff.result.succeed);
+    REQUIRE(diff.sources.to_remove.size() == 1);
+    REQUIRE(std::string(diff.sources.to_remove[0]) == "source_http_1");
+    REQUIRE(diff.transforms.to_remove.size() == 1);
+    REQUIRE(std::string(diff.transforms.to_remove[0]) == "transform_add_field_1");
+    REQUIRE(diff.sinks.to_remove.empty());
+
+    // nothing is applied by previewing
+    REQUIRE(tc->get_generation_id() == 2);
+  });
+}
+
+TEST_CASE("preview config diff of full config") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto config = load_config("http_to_file");
+    config = std::regex_replace(config, std::regex("9999"), "8888");
+    auto diff = tc->diff_config(config);
+    REQUIRE(diff.result.succeed);
+    REQUIRE(diff.sources.to_change.size() == 1);
+    REQUIRE(std::string(diff.sources.to_change[0]) == "source_http");
+    REQUIRE(diff.sinks.to_change.empty());
+    REQUIRE(diff.sinks.unchanged.size() == 1);
+
+    diff = tc->diff_config("{\"sources\": {");
+    REQUIRE(!diff.result.succeed);
+    REQUIRE(diff.result.error.kind == ErrorKind::ConfigParse);
+  });
+}
+
+TEST_CASE("preview config diff after reloading full config") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->handle_config_reload(load_config("http_to_file_with_transform")).succeed);
+    // the diff is against the reloaded config
+    auto diff = tc->diff_config_operation(
+        ConfigOperation{"add", load_config("transform/add_field"), {}, false});
+    REQUIRE(diff.result.succeed);
+    REQUIRE(diff.transforms.unchanged.size() == 1);
+    REQUIRE(std::string(diff.transforms.unchanged[0]) == "transform_remap_field");
+  });
+}
+
+TEST_CASE("get components of running topology") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    auto components = tc->get_components();
+    REQUIRE(components.size() == 3);
+    REQUIRE(std::string(components[0].id) == "source_http");
+    REQUIRE(std::string(components[0].kind) == "source");
+    REQUIRE(std::string(components[0].component_type) == "http_server");
+    REQUIRE(components[0].inputs.empty());
+    REQUIRE(components[0].generation_id == 1);
+    REQUIRE(std::string(components[1].id) == "transform_remap_field");
+    REQUIRE(std::string(components[1].kind) == "transform");
+    REQUIRE(std::string(components[1].component_type) == "remap");
+    REQUIRE(components[1].inputs.size() == 1);
+    REQUIRE(std::string(components[1].inputs[0]) == "source_*");
+    REQUIRE(std::string(components[2].id) == "sink_file");
+    REQUIRE(std::string(components[2].kind) == "sink");
+    REQUIRE(std::string(components[2].component_type) == "file");
+
+    // only the added or updated component has a new generation
+    auto config = load_config("transform/add_field");
+    REQUIRE(tc->add_config(config).succeed);
+    config = std::regex_replace(config, std::regex("42"), "43");
+    REQUIRE(tc->update_config(config).succeed);
+    REQUIRE(tc->get_generation_id() == 3);
+    components = tc->get_components();
+    REQUIRE(components.size() == 4);
+    REQUIRE(components[0].generation_id == 1);
+    REQUIRE(components[1].generation_id == 1);
+    REQUIRE(std::string(components[2].id) == "transform_add_field");
+    REQUIRE(components[2].generation_id == 3);
+    REQUIRE(components[3].generation_id == 1);
+  });
+}
+
+TEST_CASE("get config of running topology") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("source/http")).succeed);
+    auto result = tc->get_config();
+    REQUIRE(result.result.succeed);
+    auto config = nlohmann::json::parse(std::string(result.config));
+    REQUIRE(config["sources"].contains("source_file"));
+    REQUIRE(config["sources"]["source_http"]["type"] == "http_server");
+    REQUIRE(config["sinks"]["sink_file"]["inputs"][0] == "source_*");
+
+    // the config could be used to start another topology
+    REQUIRE(tc->validate_config(result.config, false).valid);
+  });
+
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->get_components().empty());
+  REQUIRE(tc->get_config().result.error.kind == ErrorKind::NotStarted);
+}
+
+TEST_CASE("rollback to config of previous generation") {
+  run("http_to_file_with_transform", [](rust::Box<TopologyController> &tc) {
+    REQUIRE(tc->add_config(load_config("transform/add_field")).succeed);
+    REQUIRE(tc->delete_config({"transform_remap_field"}, false).result.succeed);
+
+    auto history = tc->get_config_history();
+    REQUIRE(history.size() == 3);
+    REQUIRE(history[0].generation_id == 1);
+    REQUIRE(history[2].generation_id == 3);
+    REQUIRE(nlohmann::json::parse(std::string(history[0].config))["transforms"].size() == 1);
+    REQUIRE(nlohmann::json::parse(std::string(history[1].config))["transforms"].size() == 2);
+
+    auto result = tc->rollback_to(1);
+    REQUIRE(result.succeed);
+    REQUIRE(result.generation_id == 4);
+    REQUIRE(tc->get_config_history().size() == 4);
+    auto components = tc->get_components();
+    REQUIRE(components.size() == 3);
+    REQUIRE(std::string(components[1].id) == "transform_remap_field");
+    send_http_events({"e0"});
+  });
+  auto events = read_events_from_sink();
+  REQUIRE(events.size() == 1);
+  REQUIRE_THAT(events[0], ContainsSubstring("my_source"));
+  REQUIRE_THAT(events[0], !ContainsSubstring("42"));
+}
+
+TEST_CASE("rollback to generation not in config history") {
+  run("file_to_file", [](rust::Box<TopologyController> &tc) {
+    tc->set_max_config_history(2);
+    auto config = load_config("source/http");
+    REQUIRE(tc->add_config(config).succeed);
+    REQUIRE(tc->update_config(config).succeed);
+    auto history = tc->get_config_history();
+    REQUIRE(history.size() == 2);
+    REQUIRE(history[0].generation_id == 2);
+
+    auto result = tc->rollback_to(1);
+    REQUIRE(!result.succeed);
+    REQUIRE(result.error.kind == ErrorKind::GenerationNotFound);
+    REQUIRE(tc->get_generation_id() == 3);
+  });
+}
+
+TEST_CASE("reload full config advances generation") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto result = tc->handle_config_reload(load_config("http_to_file_with_transform"));
+    REQUIRE(result.succeed);
+    REQUIRE(result.generation_id == 2);
+    REQUIRE(tc->get_config_history().size() == 2);
+  });
+}
+
+TEST_CASE("reload full config interpolates environment variables") {
+  setenv("VECTORCXX_TEST_SINK_PATH", "/tmp/vector_test_env_sink.log", 1);
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto config = std::regex_replace(load_config("http_to_file_with_transform"),
+                                     std::regex("/tmp/vector_test_sink.log"), "${VECTORCXX_TEST_SINK_PATH}");
+    REQUIRE(tc->validate_config(config, false).valid);
+    REQUIRE(tc->diff_config(config).result.succeed);
+    REQUIRE(tc->handle_config_reload(config).succeed);
+    auto reloaded = nlohmann::json::parse(std::string(tc->get_config().config));
+    REQUIRE(reloaded["sinks"]["sink_file"]["path"] == "/tmp/vector_test_env_sink.log");
+  });
+  unsetenv("VECTORCXX_TEST_SINK_PATH");
+}
+
+TEST_CASE("start with persisted config after restart") {
+  setup();
+  {
+    auto tc = vectorcxx::new_topology_controller();
+    REQUIRE(tc->start_with_persisted_config(load_config("file_to_file")).succeed);
+    REQUIRE(tc->add_config(load_config("source/http")).succeed);
+    REQUIRE(tc->get_generation_id() == 2);
+    REQUIRE(tc->stop().succeed);
+  }
+  {
+    auto tc = vectorcxx::new_topology_controller();
+    auto result = tc->start_with_persisted_config(load_config("file_to_file"));
+    REQUIRE(result.succeed);
+    REQUIRE(result.generation_id == 2);
+    REQUIRE(tc->get_components().size() == 3);
+    wait();
+    send_http_events({"hello"});
+    REQUIRE(tc->stop().succeed);
+  }
+  REQUIRE(read_events_from_sink().size() == 1);
+}
+
+TEST_CASE("start with given config if persisted config fails to start") {
+  setup();
+  // the persisted config fails to build because of the missing input
+  auto persisted_config = nlohmann::json::parse(load_config("file_to_file"));
+  persisted_config["transforms"] = nlohmann::json::parse(load_config("transform/missing_input"))["transforms"];
+  nlohmann::json state = {{"generation_id", 5}, {"config", persisted_config}};
+  std::ofstream("/tmp/vector/vectorcxx_config_state.json") << state.dump();
+
+  auto tc = vectorcxx::new_topology_controller();
+  auto result = tc->start_with_persisted_config(load_config("file_to_file"));
+  REQUIRE(result.succeed);
+  REQUIRE(result.generation_id == 6);
+  REQUIRE(tc->get_components().size() == 2);
+  REQUIRE(tc->stop().succeed);
+
+  // the given config is persisted as the last committed config
+  auto persisted = nlohmann::json::parse(std::ifstream("/tmp/vector/vectorcxx_config_state.json"));
+  REQUIRE(persisted["generation_id"] == 6);
+}
+
+TEST_CASE("start with persisted config requires data dir") {
+  auto config = nlohmann::json::parse(load_config("http_to_file"));
+  config.erase("data_dir");
+  auto tc = vectorcxx::new_topology_controller();
+  auto result = tc->start_with_persisted_config(config.dump());
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigBuild);
+}
+
+TEST_CASE("async operations complete in submitted order") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  auto start_id = tc->start_async(load_config("file_to_file"));
+  auto add_id = tc->add_config_async(load_config("source/http"));
+  REQUIRE(add_id > start_id);
+
+  auto add_status = wait_for_operation(tc, add_id);
+  REQUIRE(add_status.state == OperationState::Completed);
+  REQUIRE(add_status.result.succeed);
+  REQUIRE(add_status.result.generation_id == 2);
+  auto start_status = tc->get_operation_status(start_id);
+  REQUIRE(start_status.state == OperationState::Completed);
+  REQUIRE(start_status.result.generation_id == 1);
+
+  send_http_events({"hello"});
+  auto delete_status = wait_for_operation(tc, tc->delete_config_async({"source_http_1"}, false));
+  REQUIRE(delete_status.result.succeed);
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(read_events_from_sink().size() == 1);
+}
+
+TEST_CASE("async operation reports error") {
+  run("http_to_file", [](rust::Box<TopologyController> &tc) {
+    auto status = wait_for_operation(tc, tc->update_config_async("invalid config"));
+    REQUIRE(status.state == OperationState::Completed);
+    REQUIRE(!status.result.succeed);
+    REQUIRE(status.result.error.kind == ErrorKind::ConfigParse);
+    REQUIRE(status.result.generation_id == 1);
+  });
+}
+
+TEST_CASE("unknown async operation is not found") {
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->get_operation_status(42).state == OperationState::NotFound);
+}
+
+TEST_CASE("async operation completion callback") {
+  std::mutex mutex;
+  std::vector<OperationStatus> statuses;
+  run("http_to_file", [&](rust::Box<TopologyController> &tc) {
+    tc->set_operation_callback(std::make_unique<OperationCallback>([&](const OperationStatus &status) {
+      std::lock_guard<std::mutex> lock(mutex);
+      statuses.push_back(status);
+    }));
+    auto reload_id = tc->handle_config_reload_async(load_config("http_to_file_with_transform"));
+    wait_for_operation(tc, reload_id);
+    std::lock_guard<std::mutex> lock(mutex);
+    REQUIRE(statuses.size() == 1);
+    REQUIRE(statuses[0].operation_id == reload_id);
+    REQUIRE(statuses[0].result.succeed);
+    REQUIRE(statuses[0].result.generation_id == 2);
+  });
+}
+
+TEST_CASE("stop with timeout drains events") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  wait();
+  send_http_events({"hello", "world"});
+  auto result = tc->stop_with_timeout(5000);
+  REQUIRE(result.result.succeed);
+  REQUIRE(result.graceful);
+  REQUIRE(result.sinks.size() == 1);
+  REQUIRE(result.sinks[0].sink_id == "sink_file");
+  REQUIRE(result.sinks[0].dropped_events == 0);
+  REQUIRE(read_events_from_sink().size() == 2);
+}
+
+TEST_CASE("stop with timeout gives up stuck sink") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_unreachable_http")).succeed);
+  wait();
+  send_http_events({"hello"});
+  wait(500);
+  auto result = tc->stop_with_timeout(1000);
+  REQUIRE(result.result.succeed);
+  REQUIRE(!result.graceful);
+  REQUIRE(result.sinks.size() == 1);
+  REQUIRE(result.sinks[0].flushed_events == 0);
+  REQUIRE(result.sinks[0].dropped_events == 1);
+
+  // the aborted components release the port of the source
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  wait();
+  send_http_events({"again"});
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(read_events_from_sink().size() == 1);
+}
+
+TEST_CASE("drain stats exclude events of a previous topology") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_unreachable_http")).succeed);
+  wait();
+  send_http_events({"hello"});
+  wait(500);
+  REQUIRE(!tc->stop_with_timeout(1000).graceful);
+
+  // the same sink id is started again, the event dropped before is not counted
+  REQUIRE(tc->start(load_config("http_to_unreachable_http")).succeed);
+  wait();
+  auto result = tc->stop_with_timeout(1000);
+  REQUIRE(result.sinks.size() == 1);
+  REQUIRE(result.sinks[0].dropped_events == 0);
+}
+
+TEST_CASE("stop without running topology") {
+  auto tc = vectorcxx::new_topology_controller();
+  auto result = tc->stop_with_timeout(1000);
+  REQUIRE(result.result.succeed);
+  REQUIRE(result.graceful);
+  REQUIRE(result.sinks.empty());
+}
+
+TEST_CASE("controller could be started again after stop but not after exit") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  REQUIRE(tc->exit().succeed);
+  auto result = tc->start(load_config("http_to_file"));
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::Exited);
+}
+
+TEST_CASE("topology status of running components") {
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(!tc->get_topology_status().running);
+
+  setup();
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  auto status = tc->get_topology_status();
+  REQUIRE(status.running);
+  REQUIRE(!status.crashed);
+  REQUIRE(status.generation_id == 1);
+  REQUIRE(status.components.size() == 2);
+  for (const auto &component : status.components) {
+    REQUIRE(component.health == ComponentHealth::Running);
+  }
+
+  REQUIRE(tc->add_config(load_config("transform/add_field")).succeed);
+  REQUIRE(tc->get_topology_status().components.size() == 3);
+  // the tasks replaced by reloading exit, which does not affect the respawned components
+  auto sink_config = load_config("sink/file");
+  REQUIRE(tc->add_config(sink_config).succeed);
+  sink_config = std::regex_replace(sink_config, std::regex("\"json\""), "\"text\"");
+  REQUIRE(tc->update_config(sink_config).succeed);
+  wait();
+  for (const auto &component : tc->get_topology_status().components) {
+    REQUIRE(component.health == ComponentHealth::Running);
+  }
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(!tc->get_topology_status().running);
+}
+
+TEST_CASE("topology status reports finished sources") {
+  setup();
+  std::ofstream("/tmp/vector_test_source_one_shot.log") << "hello" << std::endl;
+  std::atomic<bool> notified_finished = false;
+  auto tc = vectorcxx::new_topology_controller();
+  tc->set_health_callback(std::make_unique<HealthCallback>([&](const TopologyStatus &status) {
+    if (status.sources_finished) {
+      notified_finished = true;
+    }
+  }));
+  REQUIRE(tc->start(load_config("batch_file_to_file")).succeed);
+  for (int i = 0; i < 50 && !notified_finished; i++) {
+    wait(100);
+  }
+  REQUIRE(notified_finished);
+
+  auto status = tc->get_topology_status();
+  REQUIRE(status.sources_finished);
+  REQUIRE(!status.crashed);
+  for (const auto &component : status.components) {
+    if (std::string(component.id) == "source_file") {
+      REQUIRE(component.health == ComponentHealth::Finished);
+    }
+  }
+  REQUIRE(tc->stop().succeed);
+}
+
+// the status is changed while the controller locks are held, like starting or reloading the topology
+TEST_CASE("health callback is able to call the controller") {
+  setup();
+  std::atomic<int> notified = 0;
+  auto tc = vectorcxx::new_topology_controller();
+  TopologyController *controller = &*tc;
+  tc->set_health_callback(std::make_unique<HealthCallback>([&notified, controller](const TopologyStatus &status) {
+    controller->get_components();
+    controller->get_config();
+    notified++;
+  }));
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  REQUIRE(tc->add_config(load_config("sink/file")).succeed);
+  for (int i = 0; i < 50 && notified < 2; i++) {
+    wait(100);
+  }
+  REQUIRE(notified >= 2);
+  REQUIRE(tc->stop().succeed);
+}
+
+// the http source of a second topology on the same port fails to bind, which crashes the topology
+TEST_CASE("crashed topology is not restarted by default") {
+  setup();
+  auto occupier = vectorcxx::new_topology_controller();
+  REQUIRE(occupier->start(load_config("http_to_file")).succeed);
+  wait();
+
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  for (int i = 0; i < 50 && !tc->get_topology_status().crashed; i++) {
+    wait(100);
+  }
+  auto status = tc->get_topology_status();
+  REQUIRE(status.crashed);
+  for (const auto &component : status.components) {
+    if (std::string(component.id) == "source_http") {
+      REQUIRE(component.health == ComponentHealth::Failed);
+    }
+  }
+  REQUIRE(tc->get_restart_status().attempts == 0);
+  REQUIRE(tc->get_generation_id() == 1);
+
+  // the components of the occupier with the same ids are not affected
+  auto occupier_status = occupier->get_topology_status();
+  REQUIRE(!occupier_status.crashed);
+  for (const auto &component : occupier_status.components) {
+    REQUIRE(component.health == ComponentHealth::Running);
+  }
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(occupier->stop().succeed);
+}
+
+TEST_CASE("restart crashed topology with backoff") {
+  setup();
+  auto occupier = vectorcxx::new_topology_controller();
+  REQUIRE(occupier->start(load_config("http_to_file")).succeed);
+  wait();
+
+  auto tc = vectorcxx::new_topology_controller();
+  tc->set_restart_policy(RestartPolicy{3, 100, 400});
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  for (int i = 0; i < 50 && tc->get_restart_status().restarts == 0; i++) {
+    wait(100);
+  }
+  auto restart_status = tc->get_restart_status();
+  REQUIRE(restart_status.restarts >= 1);
+  REQUIRE(restart_status.attempts >= restart_status.restarts);
+  REQUIRE(tc->get_generation_id() > 1);
+
+  // the port is released, so the topology keeps running once restarted
+  REQUIRE(occupier->stop().succeed);
+  auto status = tc->get_topology_status();
+  for (int i = 0; i < 50 && !(status.running && !status.crashed && !tc->get_restart_status().restarting); i++) {
+    wait(100);
+    status = tc->get_topology_status();
+  }
+  wait();
+  send_http_events({"hello"});
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(!tc->get_restart_status().restarting);
+  REQUIRE(read_events_from_sink().size() == 1);
+}
+
+TEST_CASE("stopped topology is not started again by the restart in progress") {
+  setup();
+  auto occupier = vectorcxx::new_topology_controller();
+  REQUIRE(occupier->start(load_config("http_to_file")).succeed);
+  wait();
+
+  auto tc = vectorcxx::new_topology_controller();
+  tc->set_restart_policy(RestartPolicy{100, 50, 50});
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+  for (int i = 0; i < 50 && tc->get_restart_status().attempts == 0; i++) {
+    wait(100);
+  }
+  REQUIRE(tc->get_restart_status().attempts > 0);
+  REQUIRE(tc->stop().succeed);
+
+  // the port is released, so any attempt made after the stop would keep the topology running
+  REQUIRE(occupier->stop().succeed);
+  wait(500);
+  REQUIRE(!tc->get_topology_status().running);
+  REQUIRE(!tc->get_restart_status().restarting);
+}
+
+TEST_CASE("run independent controllers concurrently") {
+  setup();
+  // catch2 assertions are not thread safe, so the results are checked after the threads join
+  auto run_pipeline = [](const std::string &config, uint32_t port, bool &succeed) {
+    auto tc = vectorcxx::new_topology_controller();
+    succeed = tc->start(load_config(config)).succeed;
+    wait();
+    send_http_events({"hello", "world"}, port);
+    succeed = succeed && tc->get_generation_id() == 1 && tc->stop().succeed;
+  };
+  bool first_succeed = false;
+  bool second_succeed = false;
+  std::thread first(run_pipeline, "http_to_file", 9999, std::ref(first_succeed));
+  std::thread second(run_pipeline, "http_to_second_file", 9998, std::ref(second_succeed));
+  first.join();
+  second.join();
+  REQUIRE(first_succeed);
+  REQUIRE(second_succeed);
+
+  auto events = read_events_from_sink();
+  REQUIRE(events.size() == 2);
+  REQUIRE_THAT(events[0], !ContainsSubstring("second"));
+  auto second_events = read_events_from_sink(SECOND_FILE_SINK_PATH);
+  REQUIRE(second_events.size() == 2);
+  REQUIRE_THAT(second_events[0], ContainsSubstring("second"));
+}
+
+TEST_CASE("controllers do not share components") {
+  setup();
+  auto first = vectorcxx::new_topology_controller();
+  auto second = vectorcxx::new_topology_controller();
+  REQUIRE(first->start(load_config("http_to_file")).succeed);
+  REQUIRE(second->start(load_config("http_to_second_file")).succeed);
+  REQUIRE(first->delete_config({"sink_file"}, true).result.succeed);
+  REQUIRE(second->get_components().size() == 3);
+  REQUIRE(second->get_generation_id() == 1);
+  REQUIRE(first->stop().succeed);
+  REQUIRE(second->stop().succeed);
+}
+
+// the log schema is initialized once in the process, by the first controller started, so a
+// controller with the default one is started first whatever order the tests run in
+TEST_CASE("controller requiring a different log schema is rejected") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_file")).succeed);
+
+  auto config = nlohmann::json::parse(load_config("http_to_second_file"));
+  config["log_schema"] = {{"message_key", "another_message"}};
+  auto another = vectorcxx::new_topology_controller();
+  auto result = another->start(config.dump());
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigBuild);
+
+  // nor could it be reloaded into a running topology, which is told by validating and diffing too
+  result = tc->handle_config_reload(config.dump());
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigBuild);
+  auto validation = tc->validate_config(config.dump(), false);
+  REQUIRE(!validation.valid);
+  REQUIRE(validation.errors[0].kind == ErrorKind::ConfigBuild);
+  auto diff = tc->diff_config(config.dump());
+  REQUIRE(!diff.result.succeed);
+  REQUIRE(diff.result.error.kind == ErrorKind::ConfigBuild);
+  REQUIRE(tc->stop().succeed);
+}
+
+TEST_CASE("memory queue sinks are run by one controller at a time") {
+  setup();
+  auto tc = vectorcxx::new_topology_controller();
+  REQUIRE(tc->start(load_config("http_to_memory_queue")).succeed);
+
+  auto config = nlohmann::json::parse(load_config("http_to_memory_queue"));
+  config["sources"]["source_http"]["address"] = "0.0.0.0:9998";
+  auto another = vectorcxx::new_topology_controller();
+  auto result = another->start(config.dump());
+  REQUIRE(!result.succeed);
+  REQUIRE(result.error.kind == ErrorKind::ConfigBuild);
+  REQUIRE_THAT(std::string(result.error.message), ContainsSubstring("memory_queue"));
+  auto validation = another->validate_config(config.dump(), false);
+  REQUIRE(!validation.valid);
+  REQUIRE_THAT(std::string(validation.errors[0].message), ContainsSubstring("memory_queue"));
+
+  // it is released once the controller stops
+  REQUIRE(tc->stop().succeed);
+  REQUIRE(another->validate_config(config.dump(), false).valid);
+  REQUIRE(another->start(config.dump()).succeed);
+  REQUIRE(another->stop().succeed);
+}
@@ -17,6 +17,8 @@ using vectorcxx::CxxLogEvent;
 namespace vectorcxx::test {
 
   inline auto DATA_DIR = std::filesystem::path("/tmp/vector");
+  // the data dir used by testing configs of a second controller
+  inline auto SECOND_DATA_DIR = std::filesystem::path("/tmp/vector_2");
 
   // get a file path under data folder
   std::filesystem::path _file_path(const std::string &file_name) {
@@ -59,16 +61,30 @@ namespace vectorcxx::test {
     }
   }
 
-  static void _setup() {
+  void setup() {
     // ensure the file sink is cleared
     std::filesystem::remove(FILE_SINK_PATH);
+    std::filesystem::remove(SECOND_FILE_SINK_PATH);
     std::filesystem::remove_all(DATA_DIR);
     std::filesystem::create_directory(DATA_DIR);
+    std::filesystem::remove_all(SECOND_DATA_DIR);
+    std::filesystem::create_directory(SECOND_DATA_DIR);
+  }
+
+  vectorcxx::OperationStatus wait_for_operation(rust::Box<vectorcxx::TopologyController> &tc,
+                                                uint64_t operation_id, uint32_t timeout_ms) {
+    auto deadline = std::chrono::steady_clock::now() + std::chrono::milliseconds(timeout_ms);
+    auto status = tc->get_operation_status(operation_id);
+    while (status.state == vectorcxx::OperationState::Pending && std::chrono::steady_clock::now() < deadline) {
+      wait(10);
+      status = tc->get_operation_status(operation_id);
+    }
+    return status;
   }
 
   struct VectorService {
     explicit VectorService(const std::string &config_file) {
-      _setup();
+      setup();
       auto config = rust::String(load_config(config_file));
       spdlog::info("starting vector");
 
@@ -95,12 +111,12 @@ namespace vectorcxx::test {
 
   struct OneShotVectorService {
     explicit OneShotVectorService(const std::string &config_file) {
-      _setup();
+      setup();
       auto config = rust::String(load_config(config_file));
       spdlog::info("starting vector");
 
       tc = vectorcxx::new_one_shot_topology_controller();
-      tc.value()->start(config);
+      start_result = tc.value()->start(config);
     }
 
     ~OneShotVectorService() {}
@@ -110,6 +126,7 @@ namespace vectorcxx::test {
     }
 
     std::optional<rust::Box<vectorcxx::OneShotTopologyController>> tc;
+    vectorcxx::ControllerResult start_result;
   };
 
   void run(const std::string &config_file,
@@ -118,7 +135,7 @@ namespace vectorcxx::test {
     operations(vector_service.get_controller());
   }
 
-  void run_one_shot(
+  vectorcxx::ControllerResult run_one_shot(
       const std::string &config_file,
       const std::function<void(rust::Box<vectorcxx::OneShotTopologyController> &)> &operations) {
     std::filesystem::copy_file(_file_path("test_files/vector_test_source_one_shot.log"),
@@ -126,5 +143,6 @@ namespace vectorcxx::test {
                                copy_options::overwrite_existing);
     OneShotVectorService vector_service(config_file);
     operations(vector_service.get_controller());
+    return vector_service.start_result;
   }
 } // namespace vectorcxx::test
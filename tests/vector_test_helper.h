@@ -7,6 +7,8 @@
 namespace vectorcxx::test {
   // this is the default file sink used by all testing configs
   inline auto FILE_SINK_PATH = std::filesystem::path("/tmp/vector_test_sink.log");
+  // the file sink used by testing configs adding a second sink
+  inline auto SECOND_FILE_SINK_PATH = std::filesystem::path("/tmp/vector_test_sink_2.log");
 
   std::vector<std::string>
   read_events_from_sink(const std::filesystem::path &file_path = FILE_SINK_PATH);
@@ -17,6 +19,13 @@ namespace vectorcxx::test {
 
   void wait(uint32_t milliseconds = 200);
 
+  // clear the file sinks and the data dir used by testing configs
+  void setup();
+
+  // poll the status of an async operation until it is completed or the timeout is reached
+  vectorcxx::OperationStatus wait_for_operation(rust::Box<vectorcxx::TopologyController> &tc,
+                                                uint64_t operation_id, uint32_t timeout_ms = 10000);
+
   /**
    * run a topology with some operations performed during running
    * After running, the vector service will be stopped so that all the events are flushed and can be
@@ -25,7 +34,11 @@ namespace vectorcxx::test {
   void run(const std::string &config_file,
            const std::function<void(rust::Box<vectorcxx::TopologyController> &)> &operations);
 
-  void run_one_shot(const std::string &config_file,
-           const std::function<void(rust::Box<vectorcxx::OneShotTopologyController> &)> &operations);
+  /**
+   * run a one shot topology and return the result of starting it
+   */
+  vectorcxx::ControllerResult
+  run_one_shot(const std::string &config_file,
+               const std::function<void(rust::Box<vectorcxx::OneShotTopologyController> &)> &operations);
 
 } // namespace
\ No newline at end of file